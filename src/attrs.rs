@@ -0,0 +1,163 @@
+//! Typed `#[kanitool::*]` attributes parsed from their raw text.
+//!
+//! kani's proc macros expand `#[kani::xxx]` into `#[kanitool::xxx]`, which is what
+//! rustc reports, e.g. `#[kanitool::unwind(4)]` or `#[kanitool::proof_for_contract = "f"]`.
+
+use serde::{Deserialize, Serialize};
+
+/// Names of attributes that make a function a proof.
+pub const PROOF_ATTRS: &[&str] = &["proof", "proof_for_contract"];
+
+/// kanitool attributes on a function.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct KaniAttrs {
+    /// `#[kani::proof]`
+    pub proof: bool,
+    /// Target of `#[kani::proof_for_contract(target)]`.
+    pub proof_for_contract: Option<String>,
+    /// `#[kani::unwind(n)]`
+    pub unwind: Option<u32>,
+    /// Arguments of `#[kani::solver(...)]`, e.g. `kissat` or `bin = "path"`.
+    pub solver: Option<String>,
+    /// `#[kani::should_panic]`
+    pub should_panic: bool,
+    /// `#[kani::stub(original, replacement)]`
+    pub stubs: Vec<Stub>,
+    /// Targets of `#[kani::stub_verified(target)]`, replaced by their contracts.
+    pub stub_verified: Vec<String>,
+    /// `#[kani::recursion]`
+    pub recursion: bool,
+    /// Clauses of `#[kani::requires(...)]`.
+    pub requires: Vec<String>,
+    /// Clauses of `#[kani::ensures(...)]`.
+    pub ensures: Vec<String>,
+    /// Clauses of `#[kani::modifies(...)]`.
+    pub modifies: Vec<String>,
+    /// Clauses of `#[kani::loop_invariant(...)]`.
+    pub loop_invariant: Vec<String>,
+    /// Other kanitool attributes in raw text, e.g. ones generated for contracts.
+    pub other: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Stub {
+    pub original: String,
+    pub replacement: String,
+}
+
+impl KaniAttrs {
+    /// Parse raw attribute text. Non-kanitool attributes are ignored, and unrecognized or
+    /// malformed kanitool attributes are kept in [`KaniAttrs::other`].
+    pub fn parse(attrs: &[String]) -> Self {
+        let mut this = KaniAttrs::default();
+        for raw in attrs {
+            let Some(attr) = KaniAttr::parse(raw) else { continue };
+            if !this.add(&attr) {
+                this.other.push(raw.clone());
+            }
+        }
+        this
+    }
+
+    /// Returns false if the attribute isn't recognized.
+    fn add(&mut self, attr: &KaniAttr) -> bool {
+        let value = || attr.value.clone();
+        match (attr.name, attr.value.as_deref()) {
+            ("proof", None) => self.proof = true,
+            ("proof_for_contract", Some(_)) => self.proof_for_contract = value(),
+            ("unwind", Some(n)) => match n.parse() {
+                Ok(n) => self.unwind = Some(n),
+                Err(_) => return false,
+            },
+            ("solver", Some(_)) => self.solver = value(),
+            ("should_panic", None) => self.should_panic = true,
+            ("stub", Some(args)) => match split_args(args)[..] {
+                [original, replacement] => self.stubs.push(Stub {
+                    original: original.to_owned(),
+                    replacement: replacement.to_owned(),
+                }),
+                _ => return false,
+            },
+            ("stub_verified", Some(target)) => self.stub_verified.push(target.to_owned()),
+            ("recursion", None) => self.recursion = true,
+            ("requires", Some(clause)) => self.requires.push(clause.to_owned()),
+            ("ensures", Some(clause)) => self.ensures.push(clause.to_owned()),
+            ("modifies", Some(clause)) => self.modifies.push(clause.to_owned()),
+            ("loop_invariant", Some(clause)) => self.loop_invariant.push(clause.to_owned()),
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn is_proof(&self) -> bool {
+        self.proof || self.proof_for_contract.is_some()
+    }
+
+    /// Does kani need `-Zstubbing` for the proof?
+    pub fn needs_stubbing(&self) -> bool {
+        !self.stubs.is_empty() || !self.stub_verified.is_empty()
+    }
+
+    /// Does kani need `-Zfunction-contracts` for the proof?
+    pub fn needs_function_contracts(&self) -> bool {
+        self.proof_for_contract.is_some() || !self.stub_verified.is_empty()
+    }
+}
+
+/// A kanitool attribute split into its name and value.
+struct KaniAttr<'a> {
+    name: &'a str,
+    /// Text in the parentheses, or the string literal after `=` without quotes.
+    value: Option<String>,
+}
+
+impl<'a> KaniAttr<'a> {
+    fn parse(raw: &'a str) -> Option<Self> {
+        let attr = raw.trim().strip_prefix("#[")?.strip_suffix(']')?.trim();
+        let rest = attr.strip_prefix("kanitool")?.trim_start().strip_prefix("::")?.trim_start();
+        let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        let (name, rest) = rest.split_at(end);
+        let rest = rest.trim();
+        let value = if rest.is_empty() {
+            None
+        } else if let Some(args) = rest.strip_prefix('(').and_then(|r| r.strip_suffix(')')) {
+            Some(args.trim().to_owned())
+        } else {
+            let lit = rest.strip_prefix('=')?.trim();
+            Some(lit.strip_prefix('"')?.strip_suffix('"')?.to_owned())
+        };
+        Some(KaniAttr { name, value })
+    }
+}
+
+/// Name of a kanitool attribute, e.g. `unwind` for `#[kanitool::unwind(4)]`.
+pub fn attr_name(raw: &str) -> Option<&str> {
+    KaniAttr::parse(raw).map(|attr| attr.name)
+}
+
+/// Value of a kanitool attribute if it has the name, e.g. `__kani_replace_f` for
+/// `#[kanitool::replaced_with = "__kani_replace_f"]` and `replaced_with`.
+pub fn attr_value(raw: &str, name: &str) -> Option<String> {
+    KaniAttr::parse(raw).filter(|attr| attr.name == name)?.value
+}
+
+/// Split arguments by top-level commas, i.e. not in brackets like generic arguments.
+fn split_args(args: &str) -> Vec<&str> {
+    let mut depth = 0i32;
+    let mut start = 0;
+    let mut v = Vec::new();
+    for (i, c) in args.char_indices() {
+        match c {
+            '(' | '[' | '{' | '<' => depth += 1,
+            ')' | ']' | '}' | '>' => depth -= 1,
+            ',' if depth == 0 => {
+                v.push(args[start..i].trim());
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    v.push(args[start..].trim());
+    v.retain(|arg| !arg.is_empty());
+    v
+}
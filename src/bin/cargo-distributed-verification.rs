@@ -0,0 +1,27 @@
+//! `cargo distributed-verification`: analyze proofs in all workspace crates.
+//!
+//! This binary only drives cargo, and the analysis is done by the
+//! distributed-verification binary as a rustc wrapper.
+
+use clap::{Parser, Subcommand};
+use distributed_verification::{cargo::CargoArgs, logger};
+use eyre::Result;
+
+#[derive(Parser, Debug)]
+#[command(bin_name = "cargo", version, about, long_about = None)]
+struct Cargo {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Analyze proofs in all workspace crates, and write one JSON per crate.
+    DistributedVerification(CargoArgs),
+}
+
+fn main() -> Result<()> {
+    logger::init();
+    let Command::DistributedVerification(args) = Cargo::parse().command;
+    args.run()
+}
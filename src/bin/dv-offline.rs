@@ -0,0 +1,69 @@
+//! Post-process JSON emitted by distributed-verification.
+//!
+//! Unlike the main binary, this one doesn't link to rustc_private crates,
+//! so it can be installed with `cargo install --path . --bin dv-offline`
+//! on machines without rustc-dev and kani.
+
+use clap::{Parser, Subcommand};
+use distributed_verification::{
+    deps::DepsArgs,
+    diff::DiffArgs,
+    distribute::{CoordinatorArgs, WorkerArgs},
+    filter::FilterArgs,
+    logger,
+    merge::MergeArgs,
+    result_cache::CacheArgs,
+    run::RunArgs,
+    shard::ShardArgs,
+    summary::SummaryArgs,
+};
+use eyre::Result;
+
+/// Work on proof JSON without rustc.
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compare two proof JSON files and list proofs to rerun.
+    Diff(DiffArgs),
+    /// Merge proof JSON files into one.
+    Merge(MergeArgs),
+    /// Only keep proofs that satisfy given conditions.
+    Filter(FilterArgs),
+    /// Count proofs and callees.
+    Summary(SummaryArgs),
+    /// Split proofs into shards balanced by estimated cost.
+    Shard(ShardArgs),
+    /// Order contract proofs before proofs using their contracts through `stub_verified`,
+    /// and report contracts that are missing, changed or failed.
+    Deps(DepsArgs),
+    /// Run kani on proofs in parallel.
+    Run(RunArgs),
+    /// Look up, insert and garbage-collect cached verification results.
+    Cache(CacheArgs),
+    /// Serve proofs to workers and write a merged run report.
+    Coordinator(CoordinatorArgs),
+    /// Pull proofs from a coordinator and run kani on them.
+    Worker(WorkerArgs),
+}
+
+fn main() -> Result<()> {
+    logger::init();
+    match Args::parse().command {
+        Command::Diff(args) => args.run(),
+        Command::Merge(args) => args.run(),
+        Command::Filter(args) => args.run(),
+        Command::Summary(args) => args.run(),
+        Command::Shard(args) => args.run(),
+        Command::Deps(args) => args.run(),
+        Command::Run(args) => args.run(),
+        Command::Cache(args) => args.run(),
+        Command::Coordinator(args) => args.run(),
+        Command::Worker(args) => args.run(),
+    }
+}
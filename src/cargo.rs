@@ -0,0 +1,232 @@
+//! Run the analysis on every crate in a cargo workspace.
+//!
+//! `cargo distributed-verification` calls `cargo build` with
+//! * `RUSTC_WORKSPACE_WRAPPER` set to the distributed-verification binary, so only
+//!   workspace crates are analyzed, and dependencies are compiled by rustc as usual
+//! * `CARGO_ENCODED_RUSTFLAGS` set to [`kani_rustc_args`], so all crates are compiled
+//!   in the way kani does
+//! * [`ENV_OUT_DIR`] set to the directory where one JSON per crate is written
+//!
+//! When distributed-verification sees [`ENV_OUT_DIR`], it acts as the rustc wrapper.
+//!
+//! With `--std-library path/to/verify-rust-std/library`, a dummy crate is built with
+//! `-Zbuild-std` instead, and standard library crates are analyzed with `kani_core`
+//! injected through `RUSTC_WRAPPER`. Outputs of all crates are combined into
+//! [`STD_LIBRARY_JSON`] keyed by crate name.
+
+use crate::{
+    Proofs, Result, hash::HashArgs, kani_path, kani_rustc_args, kani_std_rustc_args,
+    kani_version_of, read_proofs, write_json,
+};
+use eyre::{Context, ContextCompat};
+use indexmap::IndexMap;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// An environment variable that holds the directory to write JSON files to.
+/// The distributed-verification binary acts as a rustc wrapper if it's set.
+pub const ENV_OUT_DIR: &str = "DISTRIBUTED_VERIFICATION_OUT_DIR";
+
+/// An environment variable telling the wrapper to emit simplified JSON if it's `1`.
+pub const ENV_SIMPLIFY_JSON: &str = "DISTRIBUTED_VERIFICATION_SIMPLIFY_JSON";
+
+/// An environment variable holding comma-separated crate names to analyze.
+/// All crates that reach the wrapper are analyzed if it's not set.
+pub const ENV_CRATES: &str = "DISTRIBUTED_VERIFICATION_CRATES";
+
+/// An environment variable holding the parent of verify-rust-std's library directory,
+/// which is stripped from file paths in the JSON.
+pub const ENV_STD_LIBRARY: &str = "DISTRIBUTED_VERIFICATION_STD_LIBRARY";
+
+/// An environment variable holding the kani version for the environment fingerprint.
+pub const ENV_KANI_VERSION: &str = "DISTRIBUTED_VERIFICATION_KANI_VERSION";
+
+/// File name of the combined output in `--std-library` mode.
+pub const STD_LIBRARY_JSON: &str = "std-library.json";
+
+/// Combined outputs keyed by crate name.
+pub type CrateProofs = IndexMap<String, Proofs>;
+
+/// Analyze proofs in all workspace crates, and write one JSON per crate.
+#[derive(clap::Args, Debug)]
+pub struct CargoArgs {
+    /// Directory to write JSON files to.
+    #[arg(long, default_value = "target/distributed-verification")]
+    pub out_dir: String,
+
+    /// Emit simplified JSON.
+    #[arg(long, default_value_t = false)]
+    pub simplify_json: bool,
+
+    /// Path to the distributed-verification binary. Default to the one
+    /// next to this executable, or the one in PATH.
+    #[arg(long)]
+    pub driver: Option<String>,
+
+    /// Path to verify-rust-std's library directory. If set, analyze standard
+    /// library crates built by `-Zbuild-std` instead of the current workspace.
+    #[arg(long)]
+    pub std_library: Option<String>,
+
+    /// Standard library crates to analyze in `--std-library` mode.
+    #[arg(long, value_delimiter = ',', default_value = "core,alloc,std")]
+    pub std_crates: Vec<String>,
+
+    #[command(flatten)]
+    pub hash: HashArgs,
+
+    /// Args for cargo build. `cargo distributed-verification -- [cargo_build_args]`
+    pub cargo_args: Vec<String>,
+}
+
+impl CargoArgs {
+    pub fn run(&self) -> Result<()> {
+        std::fs::create_dir_all(&self.out_dir)?;
+        let out_dir = Path::new(&self.out_dir).canonicalize()?;
+        let driver = match &self.driver {
+            Some(driver) => PathBuf::from(driver),
+            None => driver_path()?,
+        };
+        let kani_path = kani_path();
+        info!(?driver, ?out_dir, kani_path);
+
+        let mut cmd = Command::new(std::env::var("CARGO").as_deref().unwrap_or("cargo"));
+        cmd.arg("build")
+            .env(ENV_OUT_DIR, &out_dir)
+            .env(ENV_SIMPLIFY_JSON, if self.simplify_json { "1" } else { "0" })
+            .env(ENV_KANI_VERSION, kani_version_of(&kani_path))
+            .envs(self.hash.envs());
+        let rustflags = match &self.std_library {
+            Some(library) => {
+                let library = Path::new(library).canonicalize()?;
+                let library_parent = library.parent().context("No parent dir for library")?;
+                let mut build_std = self.std_crates.join(",");
+                if self.std_crates.iter().any(|c| c == "std") {
+                    // kani args set panic=abort
+                    build_std.push_str(",panic_abort");
+                }
+                cmd.current_dir(dummy_crate(&out_dir)?)
+                    .arg(format!("-Zbuild-std={build_std}"))
+                    .env("RUSTC_WRAPPER", &driver)
+                    .env("__CARGO_TESTS_ONLY_SRC_ROOT", &library)
+                    .env(ENV_CRATES, self.std_crates.join(","))
+                    .env(ENV_STD_LIBRARY, format!("{}/", library_parent.display()));
+                kani_std_rustc_args(&kani_path)?
+            }
+            None => {
+                cmd.env("RUSTC_WORKSPACE_WRAPPER", &driver);
+                kani_rustc_args(&kani_path)
+            }
+        };
+        // CARGO_ENCODED_RUSTFLAGS takes a string that separte arguments by 0x1f
+        cmd.env("CARGO_ENCODED_RUSTFLAGS", rustflags.join("\u{1f}")).args(&self.cargo_args);
+
+        let status = cmd.status().context("Failed to run cargo build")?;
+        ensure!(status.success(), "cargo build failed: {status}");
+
+        if self.std_library.is_some() {
+            return self.combine_std_crates(&out_dir);
+        }
+        for entry in std::fs::read_dir(&out_dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                println!("{}", path.display());
+            }
+        }
+        Ok(())
+    }
+
+    /// Combine outputs of standard library crates into one JSON.
+    fn combine_std_crates(&self, out_dir: &Path) -> Result<()> {
+        let mut map = CrateProofs::with_capacity(self.std_crates.len());
+        for name in &self.std_crates {
+            let target = CrateTarget { name, kind: "lib" };
+            let path = out_dir.join(target.json_name());
+            let path = path.to_str().context("Non UTF-8 path")?;
+            map.insert(name.clone(), read_proofs(path)?);
+        }
+        let path = out_dir.join(STD_LIBRARY_JSON);
+        write_json(Some(path.to_str().context("Non UTF-8 path")?), &map)?;
+        println!("{}", path.display());
+        Ok(())
+    }
+}
+
+/// Create an empty crate to drive `cargo build -Zbuild-std`.
+fn dummy_crate(out_dir: &Path) -> Result<PathBuf> {
+    let dir = out_dir.join("std-library-crate");
+    std::fs::create_dir_all(dir.join("src"))?;
+    std::fs::write(
+        dir.join("Cargo.toml"),
+        "[package]\nname = \"std-library-crate\"\nversion = \"0.0.0\"\nedition = \"2024\"\n\n[workspace]\n",
+    )?;
+    std::fs::write(dir.join("src/lib.rs"), "#![no_std]\n")?;
+    Ok(dir)
+}
+
+fn driver_path() -> Result<PathBuf> {
+    const DRIVER: &str = env!("CARGO_PKG_NAME");
+    let exe = std::env::current_exe()?;
+    let sibling = exe.parent().context("No parent dir for the executable")?.join(DRIVER);
+    if sibling.exists() {
+        return Ok(sibling);
+    }
+    let out = Command::new("which").arg(DRIVER).output()?;
+    ensure!(out.status.success(), "{DRIVER} is not found next to {exe:?} or in PATH");
+    Ok(PathBuf::from(std::str::from_utf8(&out.stdout)?.trim()))
+}
+
+/// A crate compiled by a rustc invocation from cargo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrateTarget<'a> {
+    pub name: &'a str,
+    /// `lib` for any library `--crate-type`, `test` for `--test`, or `bin`.
+    pub kind: &'a str,
+}
+
+impl<'a> CrateTarget<'a> {
+    /// Find the crate to analyze from rustc args passed by cargo.
+    ///
+    /// None means the crate should be compiled without analysis, such as
+    /// * rustc queries from cargo like `rustc -vV` and `rustc - --print=file-names`
+    /// * build scripts and proc-macro crates, since they run on the host
+    pub fn new(rustc_args: &'a [String]) -> Option<Self> {
+        let mut name = None;
+        let mut kind = None;
+        let mut iter = rustc_args.iter().map(|s| s.as_str());
+        while let Some(arg) = iter.next() {
+            match arg {
+                "--crate-name" => name = iter.next(),
+                "--crate-type" => kind = iter.next(),
+                "--test" => kind = Some("test"),
+                _ if arg.starts_with("--print") => return None,
+                _ => {
+                    if let Some(s) = arg.strip_prefix("--crate-name=") {
+                        name = Some(s);
+                    } else if let Some(s) = arg.strip_prefix("--crate-type=") {
+                        kind = Some(s);
+                    }
+                }
+            }
+        }
+        let name = name?;
+        let kind = match kind.unwrap_or("bin") {
+            "proc-macro" => return None,
+            kind @ ("bin" | "test") => kind,
+            // A crate may be built as several library types at once, e.g. std is built as
+            // rlib and dylib, so they share one name.
+            _ => "lib",
+        };
+        if name.starts_with("build_script_") {
+            return None;
+        }
+        Some(CrateTarget { name, kind })
+    }
+
+    /// JSON file name for the crate, i.e. `{crate_name}-{kind}.json`.
+    pub fn json_name(&self) -> String {
+        format!("{}-{}.json", self.name, self.kind)
+    }
+}
@@ -1,19 +1,55 @@
 use crate::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use distributed_verification::{
+    cargo::{CrateTarget, ENV_CRATES, ENV_KANI_VERSION, ENV_OUT_DIR, ENV_SIMPLIFY_JSON},
+    diff::DiffArgs,
+    hash::HashArgs,
     kani_list::{KaniList, read_kani_list},
-    kani_path,
+    kani_path, kani_rustc_args, kani_version_of,
 };
 
 /// Parse cli arguments.
-pub fn parse() -> Result<Run> {
-    Args::parse().into_args()
+pub fn parse() -> Result<Mode> {
+    if let Ok(out_dir) = std::env::var(ENV_OUT_DIR) {
+        return rustc_wrapper(&out_dir);
+    }
+    let mut args = Args::parse();
+    match args.command.take() {
+        Some(Command::Diff(diff)) => Ok(Mode::Diff(diff)),
+        None => args.into_args().map(|run| Mode::Analyze(Box::new(run))),
+    }
+}
+
+/// Cargo calls `distributed-verification path/to/rustc [rustc_args]` as a rustc wrapper,
+/// and kani args are passed through `CARGO_ENCODED_RUSTFLAGS`.
+fn rustc_wrapper(out_dir: &str) -> Result<Mode> {
+    let rustc_args: Vec<String> = std::env::args().skip(1).collect();
+    let crates = std::env::var(ENV_CRATES).ok();
+    let json = CrateTarget::new(rustc_args.get(1..).unwrap_or_default())
+        .filter(|c| crates.as_ref().is_none_or(|crates| crates.split(',').any(|s| s == c.name)))
+        .map(|c| c.json_name());
+    let Some(json) = json else {
+        return Ok(Mode::Rustc(rustc_args));
+    };
+    Ok(Mode::Analyze(Box::new(Run {
+        json: Some(format!("{out_dir}/{json}")),
+        kani_list: None,
+        kani_version: std::env::var(ENV_KANI_VERSION).ok(),
+        simplify_json: std::env::var(ENV_SIMPLIFY_JSON).as_deref() == Ok("1"),
+        // cargo needs artifacts to compile dependent crates
+        continue_compilation: true,
+        hash: HashArgs::from_env()?,
+        rustc_args,
+    })))
 }
 
 /// A helper tool for verify-rust-std repo to speed up verification.
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Possible one of these values:
     /// * `--json false`: skip serializing to json
     /// * `--json path/to/file.json`
@@ -40,40 +76,33 @@ struct Args {
     #[arg(long, default_value_t = false)]
     continue_compilation: bool,
 
+    #[command(flatten)]
+    hash: HashArgs,
+
     /// Args for rustc. `distributed-verification -- [rustc_args]`
     /// No need to pass rustc as the first argument.
     rustc_args: Vec<String>,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compare two proof JSON files and list proofs to rerun. No rustc is needed.
+    Diff(DiffArgs),
+}
+
 impl Args {
     pub fn into_args(self) -> Result<Run> {
+        let mut kani_version = None;
         let mut rustc_args = if self.no_kani_args {
             vec!["rustc".to_owned()]
         } else {
             let kani_path = kani_path();
             info!(kani_path, ?self);
-            Vec::from(
-                [
-                    // the first argument to rustc is unimportant
-                    "rustc",
-                    "--crate-type=lib",
-                    "--cfg=kani",
-                    "-Zcrate-attr=feature(register_tool)",
-                    "-Zcrate-attr=register_tool(kanitool)",
-                    "--sysroot",
-                    &kani_path,
-                    "-L",
-                    &format!("{kani_path}/lib"),
-                    "--extern",
-                    "kani",
-                    "--extern",
-                    &format!("noprelude:std={kani_path}/lib/libstd.rlib"),
-                    "-Zunstable-options",
-                    "-Zalways-encode-mir",
-                    "-Zmir-enable-passes=-RemoveStorageMarkers",
-                ]
-                .map(String::from),
-            )
+            kani_version = Some(kani_version_of(&kani_path));
+            // the first argument to rustc is unimportant
+            let mut args = vec!["rustc".to_owned(), "--crate-type=lib".to_owned()];
+            args.extend(kani_rustc_args(&kani_path));
+            args
         };
         rustc_args.extend(self.rustc_args);
 
@@ -82,17 +111,32 @@ impl Args {
         Ok(Run {
             json: self.json,
             kani_list,
+            kani_version,
             simplify_json: self.simplify_json,
             continue_compilation: self.continue_compilation,
+            hash: self.hash,
             rustc_args,
         })
     }
 }
 
+pub enum Mode {
+    /// Analyze proofs through rustc.
+    Analyze(Box<Run>),
+    /// Compare proof JSON files without rustc.
+    Diff(DiffArgs),
+    /// Run rustc without analysis as a rustc wrapper.
+    /// The first argument is the path to rustc.
+    Rustc(Vec<String>),
+}
+
 pub struct Run {
     pub json: Option<String>,
     pub kani_list: Option<KaniList>,
+    /// Kani version from the kani path, if kani args are used.
+    pub kani_version: Option<String>,
     pub simplify_json: bool,
     pub continue_compilation: bool,
+    pub hash: HashArgs,
     pub rustc_args: Vec<String>,
 }
@@ -0,0 +1,262 @@
+//! Dependencies between proofs from kanitool attributes.
+//!
+//! A proof with `#[kani::stub_verified(f)]` assumes the contract of `f` instead of verifying
+//! `f`, so it's only sound if `#[kani::proof_for_contract(f)]` passes. Targets are the def
+//! paths resolved when the JSON was emitted, so `div` in two modules are different targets.
+//! JSON files without resolved targets fall back to paths as written in attributes.
+
+use crate::{
+    ProofView, Result,
+    attrs::KaniAttrs,
+    filter::read_rerun,
+    read_proofs,
+    run::{RunReport, read_report},
+    write_json,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet},
+};
+
+/// Build the dependency graph between contract proofs and proofs using their contracts.
+#[derive(clap::Args, Debug)]
+pub struct DepsArgs {
+    /// JSON file of proofs.
+    pub file: String,
+
+    /// Report contract proofs in the rerun list of a JSON emitted by `diff` as changed.
+    #[arg(long)]
+    pub rerun: Option<String>,
+
+    /// Report contract proofs that failed in a run report JSON emitted by `run`.
+    #[arg(long)]
+    pub report: Option<String>,
+
+    /// Path to write the dependency graph JSON. Print to stdout if not set.
+    #[arg(long)]
+    pub json: Option<String>,
+}
+
+impl DepsArgs {
+    pub fn run(&self) -> Result<()> {
+        let proofs = read_proofs(&self.file)?;
+        let mut graph = ProofGraph::new(&proofs.views());
+        if let Some(rerun) = self.rerun.as_deref().map(read_rerun).transpose()? {
+            graph.check_changed(&rerun);
+        }
+        if let Some(report) = self.report.as_deref().map(read_report).transpose()? {
+            graph.check_failed(&report);
+        }
+        graph.issues.iter().for_each(DepIssue::warn);
+        info!(proofs = graph.proofs.len(), issues = graph.issues.len());
+        write_json(self.json.as_deref(), &graph)
+    }
+}
+
+/// Proofs with their contract dependencies.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ProofGraph {
+    /// Proofs in the input order.
+    pub proofs: Vec<ProofNode>,
+    /// Positions of proofs in the order to verify them: contract proofs come before
+    /// proofs using their contracts, otherwise the input order is kept.
+    /// Proofs in a dependency cycle come last.
+    pub order: Vec<usize>,
+    /// Contract dependencies that make proofs unsound.
+    pub issues: Vec<DepIssue>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ProofNode {
+    pub file: String,
+    pub name: String,
+    pub hash: String,
+    /// Def path of the `proof_for_contract` target.
+    pub contract: Option<String>,
+    /// Contracts assumed through `stub_verified`.
+    pub stub_verified: Vec<ContractDep>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ContractDep {
+    /// Def path of the `stub_verified` target.
+    pub target: String,
+    /// Positions of proofs checking the contract. Empty if the contract is never checked.
+    pub proofs: Vec<usize>,
+}
+
+/// A proof using a contract which isn't known to hold.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DepIssue {
+    /// Position of the proof using the contract.
+    pub position: usize,
+    /// Name of the proof using the contract.
+    pub name: String,
+    /// Def path of the `stub_verified` target.
+    pub target: String,
+    pub kind: IssueKind,
+    /// Name of the proof checking the contract. None if it's missing.
+    pub contract_proof: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKind {
+    /// No proof checks the contract.
+    #[default]
+    Missing,
+    /// The proof checking the contract needs to rerun.
+    Changed,
+    /// The proof checking the contract failed.
+    Failed,
+    /// The proof checking the contract depends on the proof using it.
+    Cycle,
+}
+
+impl ProofGraph {
+    pub fn new(proofs: &[ProofView]) -> Self {
+        let targets: Vec<_> = proofs.iter().map(targets).collect();
+        let checking = |target: &str| -> Vec<usize> {
+            let contracts = targets.iter().map(|(contract, _)| contract.as_deref());
+            contracts.enumerate().filter(|(_, c)| *c == Some(target)).map(|(i, _)| i).collect()
+        };
+
+        let nodes: Vec<_> = proofs
+            .iter()
+            .zip(&targets)
+            .map(|(proof, (contract, stub_verified))| ProofNode {
+                file: proof.file.to_owned(),
+                name: proof.name.to_owned(),
+                hash: proof.hash.to_owned(),
+                contract: contract.clone(),
+                stub_verified: stub_verified
+                    .iter()
+                    .map(|target| ContractDep { target: target.clone(), proofs: checking(target) })
+                    .collect(),
+            })
+            .collect();
+
+        let mut graph = ProofGraph { proofs: nodes, ..Default::default() };
+        graph.sort();
+        for (position, node) in graph.proofs.iter().enumerate() {
+            for dep in node.stub_verified.iter().filter(|dep| dep.proofs.is_empty()) {
+                graph.issues.push(DepIssue {
+                    position,
+                    name: node.name.clone(),
+                    target: dep.target.clone(),
+                    kind: IssueKind::Missing,
+                    contract_proof: None,
+                });
+            }
+        }
+        graph
+    }
+
+    /// Topological sort that prefers smaller positions, and report cycles.
+    fn sort(&mut self) {
+        let len = self.proofs.len();
+        let mut users = vec![Vec::new(); len];
+        let mut pending = vec![0usize; len];
+        for (position, node) in self.proofs.iter().enumerate() {
+            for &contract in node.stub_verified.iter().flat_map(|dep| &dep.proofs) {
+                if contract != position {
+                    users[contract].push(position);
+                    pending[position] += 1;
+                }
+            }
+        }
+
+        let mut ready: BinaryHeap<_> = (0..len).filter(|&i| pending[i] == 0).map(Reverse).collect();
+        while let Some(Reverse(position)) = ready.pop() {
+            self.order.push(position);
+            for &user in &users[position] {
+                pending[user] -= 1;
+                if pending[user] == 0 {
+                    ready.push(Reverse(user));
+                }
+            }
+        }
+
+        let in_cycle: Vec<_> = (0..len).filter(|&i| pending[i] != 0).collect();
+        self.order.extend(&in_cycle);
+        for &position in &in_cycle {
+            self.add_issues(position, IssueKind::Cycle, |contract| pending[contract] != 0);
+        }
+    }
+
+    /// Report contract proofs in the rerun list of a diff.
+    pub fn check_changed(&mut self, rerun: &HashSet<(String, String)>) {
+        let changed: Vec<_> =
+            self.proofs.iter().map(|p| rerun.contains(&(p.file.clone(), p.name.clone()))).collect();
+        for position in 0..self.proofs.len() {
+            self.add_issues(position, IssueKind::Changed, |contract| changed[contract]);
+        }
+    }
+
+    /// Positions of proofs in the rerun list, or all proofs without one, in [`Self::order`].
+    pub fn select(&self, rerun: Option<&HashSet<(String, String)>>) -> Vec<usize> {
+        let selected = self.order.iter().copied().filter(|&i| {
+            let p = &self.proofs[i];
+            rerun.is_none_or(|set| set.contains(&(p.file.clone(), p.name.clone())))
+        });
+        selected.collect()
+    }
+
+    /// Issues of the selected proofs after a run, including contract proofs failed in the
+    /// run, which are also logged.
+    pub fn run_issues(mut self, selected: &[usize], report: &RunReport) -> Vec<DepIssue> {
+        self.check_failed(report);
+        let issues: Vec<_> =
+            self.issues.into_iter().filter(|i| selected.contains(&i.position)).collect();
+        issues.iter().for_each(DepIssue::warn);
+        issues
+    }
+
+    /// Report contract proofs that failed in a run.
+    pub fn check_failed(&mut self, report: &RunReport) {
+        let failed: HashSet<_> = report
+            .results
+            .iter()
+            .filter(|r| !r.success)
+            .map(|r| (&*r.file, &*r.name, &*r.hash))
+            .collect();
+        let failed: Vec<_> =
+            self.proofs.iter().map(|p| failed.contains(&(&*p.file, &*p.name, &*p.hash))).collect();
+        for position in 0..self.proofs.len() {
+            self.add_issues(position, IssueKind::Failed, |contract| failed[contract]);
+        }
+    }
+
+    /// Add an issue for each contract proof of the proof at the position that matches.
+    fn add_issues(&mut self, position: usize, kind: IssueKind, f: impl Fn(usize) -> bool) {
+        let node = &self.proofs[position];
+        for dep in &node.stub_verified {
+            for &contract in dep.proofs.iter().filter(|&&c| c != position && f(c)) {
+                self.issues.push(DepIssue {
+                    position,
+                    name: node.name.clone(),
+                    target: dep.target.clone(),
+                    kind,
+                    contract_proof: Some(self.proofs[contract].name.clone()),
+                });
+            }
+        }
+    }
+}
+
+/// Targets of `proof_for_contract` and `stub_verified` of the proof.
+fn targets(proof: &ProofView) -> (Option<String>, Vec<String>) {
+    if proof.contract.is_some() || !proof.stub_verified.is_empty() {
+        let stub_verified = proof.stub_verified.iter().map(|s| s.to_string()).collect();
+        return (proof.contract.map(str::to_owned), stub_verified);
+    }
+    let attrs = KaniAttrs::parse(proof.attrs);
+    (attrs.proof_for_contract, attrs.stub_verified)
+}
+
+impl DepIssue {
+    pub fn warn(&self) {
+        let DepIssue { name, target, kind, contract_proof, .. } = self;
+        warn!(name, target, ?kind, ?contract_proof, "stub_verified contract isn't known to hold");
+    }
+}
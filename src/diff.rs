@@ -0,0 +1,200 @@
+//! Compare two proof JSON outputs and compute proofs that need to rerun.
+//!
+//! This module only works on the JSON types, so no rustc is needed.
+
+use crate::{Proofs, Result, SerFunction, SimplifiedSerFunction, read_proofs, write_json};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+/// Compare two JSON files emitted by `--json`, and list proofs to rerun.
+///
+/// Both full and simplified JSON are accepted, and they can be mixed.
+/// If both are full, changed functions of each hash-changed proof are reported.
+#[derive(clap::Args, Debug)]
+pub struct DiffArgs {
+    /// JSON of proofs from the old (base) revision.
+    pub old: String,
+
+    /// JSON of proofs from the new revision.
+    pub new: String,
+
+    /// Path to write the diff JSON. Print to stdout if not set.
+    #[arg(long)]
+    pub json: Option<String>,
+}
+
+impl DiffArgs {
+    pub fn run(&self) -> Result<()> {
+        let old = read_proofs(&self.old)?;
+        let new = read_proofs(&self.new)?;
+        let mut diff = ProofDiff::new(&old.to_simplified(), &new.to_simplified());
+        if let (Proofs::Full(old), Proofs::Full(new)) = (&old, &new) {
+            diff.explain(old, new);
+        }
+        info!(
+            rerun = diff.rerun.len(),
+            added = diff.added.len(),
+            removed = diff.removed.len(),
+            hash_changed = diff.hash_changed.len(),
+            unchanged = diff.unchanged.len()
+        );
+        write_json(self.json.as_deref(), &diff)
+    }
+}
+
+/// Proofs sorted into groups by comparing the old and new outputs.
+///
+/// A proof is identified by its file path and name.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ProofDiff {
+    /// Proofs that need to rerun, i.e. added and hash-changed proofs
+    /// in the order of the new output.
+    pub rerun: Vec<DiffProof>,
+    /// Proofs only in the new output.
+    pub added: Vec<DiffProof>,
+    /// Proofs only in the old output.
+    pub removed: Vec<DiffProof>,
+    /// Proofs in both outputs, but with different hash values.
+    pub hash_changed: Vec<DiffProof>,
+    /// Proofs in both outputs with the same hash value.
+    pub unchanged: Vec<DiffProof>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DiffProof {
+    pub file: String,
+    pub name: String,
+    /// Hash value in the old output. None if the proof is added.
+    pub old_hash: Option<String>,
+    /// Hash value in the new output. None if the proof is removed.
+    pub new_hash: Option<String>,
+    /// Changed functions if the hash changed and both outputs are full.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changes: Option<FnChanges>,
+}
+
+/// Functions that changed in a proof, found by comparing per-function hashes.
+///
+/// Callees are identified by their names.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct FnChanges {
+    /// Is the proof function itself changed?
+    pub proof: bool,
+    /// Callees only in the new output.
+    pub added: Vec<String>,
+    /// Callees only in the old output.
+    pub removed: Vec<String>,
+    /// Callees in both outputs, but with different hash values.
+    pub changed: Vec<String>,
+    /// Statics added, removed, or with different hash values.
+    #[serde(default)]
+    pub statics: Vec<String>,
+    /// ADTs added, removed, or with different hash values.
+    #[serde(default)]
+    pub types: Vec<String>,
+    /// Body-less callees added, removed, or with different signatures.
+    #[serde(default)]
+    pub opaque: Vec<String>,
+}
+
+impl FnChanges {
+    /// None if function hashes are missing, e.g. in JSON emitted by older versions.
+    pub fn new(old: &SerFunction, new: &SerFunction) -> Option<Self> {
+        if old.func_hash.is_empty() || new.func_hash.is_empty() {
+            return None;
+        }
+        let callees = |f: &'_ SerFunction| -> IndexMap<String, String> {
+            f.callees.iter().map(|c| (c.func.name.clone(), c.hash.clone())).collect()
+        };
+        let (map_old, map_new) = (callees(old), callees(new));
+
+        let mut changes = FnChanges { proof: old.func_hash != new.func_hash, ..Default::default() };
+        for (name, hash) in &map_new {
+            match map_old.get(name) {
+                None => changes.added.push(name.clone()),
+                Some(old_hash) if old_hash != hash => changes.changed.push(name.clone()),
+                Some(_) => (),
+            }
+        }
+        changes.removed = map_old.keys().filter(|k| !map_new.contains_key(*k)).cloned().collect();
+
+        let statics = |f: &'_ SerFunction| -> IndexMap<String, String> {
+            f.statics.iter().map(|s| (s.name.clone(), s.hash.clone())).collect()
+        };
+        changes.statics = changed_names(&statics(old), &statics(new));
+        let types = |f: &'_ SerFunction| -> IndexMap<String, String> {
+            f.types.iter().map(|t| (t.name.clone(), t.hash.clone())).collect()
+        };
+        changes.types = changed_names(&types(old), &types(new));
+        let opaque = |f: &'_ SerFunction| -> IndexMap<String, String> {
+            f.opaque.iter().map(|o| (o.name.clone(), o.hash.clone())).collect()
+        };
+        changes.opaque = changed_names(&opaque(old), &opaque(new));
+        Some(changes)
+    }
+}
+
+/// Sorted names that are added, removed, or with different hash values.
+fn changed_names(old: &IndexMap<String, String>, new: &IndexMap<String, String>) -> Vec<String> {
+    let mut names: Vec<_> = old.keys().chain(new.keys()).collect();
+    names.sort();
+    names.dedup();
+    names.into_iter().filter(|name| old.get(*name) != new.get(*name)).cloned().collect()
+}
+
+impl ProofDiff {
+    pub fn new(old: &[SimplifiedSerFunction], new: &[SimplifiedSerFunction]) -> Self {
+        let map_old: IndexMap<_, _> = old.iter().map(|f| ((&*f.file, &*f.name), f)).collect();
+        let map_new: IndexMap<_, _> = new.iter().map(|f| ((&*f.file, &*f.name), f)).collect();
+
+        let mut diff = ProofDiff::default();
+        for (&(file, name), f_new) in &map_new {
+            let old_hash = map_old.get(&(file, name)).map(|f_old| f_old.hash.clone());
+            let proof = DiffProof {
+                file: file.to_owned(),
+                name: name.to_owned(),
+                new_hash: Some(f_new.hash.clone()),
+                old_hash,
+                changes: None,
+            };
+            match &proof.old_hash {
+                None => {
+                    diff.rerun.push(proof.clone());
+                    diff.added.push(proof);
+                }
+                Some(old_hash) if *old_hash != f_new.hash => {
+                    diff.rerun.push(proof.clone());
+                    diff.hash_changed.push(proof);
+                }
+                Some(_) => diff.unchanged.push(proof),
+            }
+        }
+
+        for (&(file, name), f_old) in &map_old {
+            if !map_new.contains_key(&(file, name)) {
+                diff.removed.push(DiffProof {
+                    file: file.to_owned(),
+                    name: name.to_owned(),
+                    old_hash: Some(f_old.hash.clone()),
+                    new_hash: None,
+                    changes: None,
+                });
+            }
+        }
+
+        diff
+    }
+    /// Fill changed functions for hash-changed proofs from full outputs.
+    pub fn explain(&mut self, old: &[SerFunction], new: &[SerFunction]) {
+        let map_old: IndexMap<_, _> =
+            old.iter().map(|f| ((&*f.func.file, &*f.func.name), f)).collect();
+        let map_new: IndexMap<_, _> =
+            new.iter().map(|f| ((&*f.func.file, &*f.func.name), f)).collect();
+        for proof in self.hash_changed.iter_mut().chain(&mut self.rerun) {
+            let key = (&*proof.file, &*proof.name);
+            if let (Some(f_old), Some(f_new)) = (map_old.get(&key), map_new.get(&key)) {
+                proof.changes = FnChanges::new(f_old, f_new);
+            }
+        }
+    }
+}
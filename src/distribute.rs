@@ -0,0 +1,365 @@
+//! Distribute proofs to workers on other processes or machines.
+//!
+//! The coordinator holds a queue of proofs and listens on a TCP address.
+//! Each worker connection carries one JSON request line and gets one JSON
+//! response line: workers pull a proof, run kani on it, and push the result.
+//! A proof pulled but not pushed within the lease is handed out again,
+//! so a dead worker doesn't block the run. Once all results are in, the
+//! coordinator answers `Done` for a while before it exits, so workers can tell
+//! a finished run from a lost coordinator.
+
+use crate::{
+    Result,
+    deps::ProofGraph,
+    filter::read_rerun,
+    read_proofs,
+    result_cache::ResultCache,
+    run::{Job, ProofResult, RunReport, Runner},
+    write_json,
+};
+use eyre::{Context, ContextCompat};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{BufRead, BufReader, ErrorKind, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    time::{Duration, Instant},
+};
+
+/// Serve proofs to workers and write a merged run report.
+#[derive(clap::Args, Debug)]
+pub struct CoordinatorArgs {
+    /// JSON file of proofs, e.g. the full output, a filtered one, or a shard.
+    pub file: String,
+
+    /// Only serve proofs in the rerun list of a JSON emitted by `diff`.
+    #[arg(long)]
+    pub rerun: Option<String>,
+
+    /// Address to listen on.
+    #[arg(long, default_value = "127.0.0.1:7878")]
+    pub listen: String,
+
+    /// Seconds before a pulled proof without result is handed out again.
+    #[arg(long, default_value_t = 3600)]
+    pub lease_secs: u64,
+
+    /// Path to write the run report JSON. Print to stdout if not set.
+    #[arg(long)]
+    pub json: Option<String>,
+}
+
+impl CoordinatorArgs {
+    pub fn run(&self) -> Result<()> {
+        let proofs = read_proofs(&self.file)?;
+        let views = proofs.views();
+        // The graph is built before filtering, so contract proofs that don't rerun are known.
+        let graph = ProofGraph::new(&views);
+        let rerun = self.rerun.as_deref().map(read_rerun).transpose()?;
+        let selected = graph.select(rerun.as_ref());
+        // contract proofs are handed out before proofs using their contracts
+        let jobs = selected.iter().map(|&i| Job::new(&views[i])).collect();
+        let coordinator =
+            Coordinator::bind(&self.listen, jobs, Duration::from_secs(self.lease_secs))?;
+        info!(addr = %coordinator.local_addr()?, proofs = selected.len());
+
+        let mut report = coordinator.serve()?;
+        report.dependency_issues = graph.run_issues(&selected, &report);
+        write_json(self.json.as_deref(), &report)?;
+
+        let failed = report.results.iter().filter(|r| !r.success).count();
+        ensure!(failed == 0, "{failed} of {} proofs failed", report.results.len());
+        Ok(())
+    }
+}
+
+/// Pull proofs from a coordinator, run kani on them, and push results back.
+#[derive(clap::Args, Debug)]
+pub struct WorkerArgs {
+    /// Address of the coordinator.
+    #[arg(long, default_value = "127.0.0.1:7878")]
+    pub connect: String,
+
+    /// Worker name in logs of the coordinator. Default to the process id.
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Directory of the result cache. Cached results are pushed without running kani.
+    #[arg(long)]
+    pub cache: Option<String>,
+
+    #[command(flatten)]
+    pub runner: Runner,
+}
+
+impl WorkerArgs {
+    pub fn run(&self) -> Result<()> {
+        let worker = Worker {
+            addr: self.connect.clone(),
+            name: self.name.clone().unwrap_or_else(|| format!("worker-{}", std::process::id())),
+            cache: self.cache.as_deref().map(ResultCache::new),
+            runner: self.runner.clone(),
+        };
+        let ran = worker.run()?;
+        info!(ran);
+        Ok(())
+    }
+}
+
+/// A request from a worker.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Request {
+    /// Ask for a proof to run.
+    Pull { worker: String },
+    /// Send back the result of a proof.
+    Push {
+        worker: String,
+        position: usize,
+        kani_version: String,
+        #[serde(default)]
+        kani_args: Vec<String>,
+        result: ProofResult,
+    },
+}
+
+/// A response from the coordinator.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Response {
+    /// A proof to run. `position` is used to push the result.
+    Job { position: usize, job: Job },
+    /// All proofs are handed out, but some results are pending. Pull again later.
+    Wait,
+    /// All results are in. The worker can exit.
+    Done,
+    /// The result is received.
+    Ack,
+}
+
+/// Send a request and read the response through a new connection.
+pub fn request(addr: &str, req: &Request) -> Result<Response> {
+    let mut stream =
+        TcpStream::connect(addr).with_context(|| format!("Failed to connect {addr}"))?;
+    let mut line = serde_json::to_string(req)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    serde_json::from_str(&line).with_context(|| format!("Invalid response {line:?}"))
+}
+
+pub struct Coordinator {
+    listener: TcpListener,
+    jobs: Vec<Job>,
+    lease: Duration,
+    /// Next position never handed out.
+    next: usize,
+    /// When each position is handed out last time.
+    leased: Vec<Option<Instant>>,
+    results: Vec<Option<ProofResult>>,
+    /// kani version and args of the first pushed result.
+    kani: Option<(String, Vec<String>)>,
+}
+
+impl Coordinator {
+    /// How long to keep answering requests after all results are in.
+    /// Longer than the interval of workers pulling again after `Wait`.
+    const LINGER: Duration = Duration::from_secs(3);
+
+    pub fn bind(addr: &str, jobs: Vec<Job>, lease: Duration) -> Result<Self> {
+        let listener = TcpListener::bind(addr).with_context(|| format!("Failed to bind {addr}"))?;
+        let len = jobs.len();
+        Ok(Coordinator {
+            listener,
+            jobs,
+            lease,
+            next: 0,
+            leased: vec![None; len],
+            results: vec![None; len],
+            kani: None,
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    fn finished(&self) -> bool {
+        self.results.iter().all(Option::is_some)
+    }
+
+    /// Serve workers until results of all proofs are pushed.
+    pub fn serve(mut self) -> Result<RunReport> {
+        while !self.finished() {
+            let (stream, peer) = self.listener.accept()?;
+            let _span = debug_span!("serve", %peer).entered();
+            if let Err(err) = self.handle(stream) {
+                warn!(?err, "Failed to handle request");
+            }
+        }
+        self.linger()?;
+        let results = self.results.into_iter().map(Option::unwrap).collect();
+        let (kani_version, kani_args) = self.kani.unwrap_or_default();
+        Ok(RunReport { kani_version, kani_args, results, dependency_issues: Vec::new() })
+    }
+
+    /// Answer requests for [`Self::LINGER`], so waiting workers get `Done`.
+    fn linger(&mut self) -> Result<()> {
+        self.listener.set_nonblocking(true)?;
+        let deadline = Instant::now() + Self::LINGER;
+        while Instant::now() < deadline {
+            match self.listener.accept() {
+                Ok((stream, peer)) => {
+                    let _span = debug_span!("linger", %peer).entered();
+                    stream.set_nonblocking(false)?;
+                    if let Err(err) = self.handle(stream) {
+                        warn!(?err, "Failed to handle request");
+                    }
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                    std::thread::sleep(Duration::from_millis(50))
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(())
+    }
+
+    fn handle(&mut self, stream: TcpStream) -> Result<()> {
+        stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+        let mut reader = BufReader::new(&stream);
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let req: Request = serde_json::from_str(&line)?;
+
+        let resp = match req {
+            Request::Pull { worker } => match self.pull() {
+                Some(position) => {
+                    info!(worker, name = self.jobs[position].name, "Hand out");
+                    Response::Job { position, job: self.jobs[position].clone() }
+                }
+                None if self.finished() => Response::Done,
+                None => Response::Wait,
+            },
+            Request::Push { worker, position, kani_version, kani_args, result } => {
+                info!(worker, name = result.name, success = result.success, "Receive");
+                self.push(position, (kani_version, kani_args), result)?;
+                Response::Ack
+            }
+        };
+        let mut line = serde_json::to_string(&resp)?;
+        line.push('\n');
+        (&stream).write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Hand out a proof never handed out, or one whose lease expires.
+    fn pull(&mut self) -> Option<usize> {
+        let position = if self.next < self.jobs.len() {
+            self.next += 1;
+            self.next - 1
+        } else {
+            let now = Instant::now();
+            (0..self.jobs.len()).find(|&i| {
+                self.results[i].is_none()
+                    && self.leased[i].is_some_and(|t| now.duration_since(t) >= self.lease)
+            })?
+        };
+        self.leased[position] = Some(Instant::now());
+        Some(position)
+    }
+
+    fn push(
+        &mut self,
+        position: usize,
+        kani: (String, Vec<String>),
+        result: ProofResult,
+    ) -> Result<()> {
+        let job =
+            self.jobs.get(position).with_context(|| format!("Invalid position {position}"))?;
+        ensure!(job.hash == result.hash, "Result of {} doesn't match the proof hash", job.name);
+        match &self.kani {
+            Some(first) if *first != kani => {
+                warn!(expected = ?first, found = ?kani, "Workers use different kani versions or args")
+            }
+            Some(_) => (),
+            None => self.kani = Some(kani),
+        }
+        // keep the first result if a proof is handed out again due to lease expiration
+        self.results[position].get_or_insert(result);
+        Ok(())
+    }
+}
+
+pub struct Worker {
+    pub addr: String,
+    pub name: String,
+    pub cache: Option<ResultCache>,
+    pub runner: Runner,
+}
+
+impl Worker {
+    /// Max attempts to connect before the coordinator is up.
+    const CONNECT_ATTEMPTS: usize = 30;
+
+    /// Pull and run proofs until the coordinator says done.
+    /// Return the count of proofs this worker pushed.
+    ///
+    /// Failing to reach the coordinator after the first response is an error,
+    /// since the coordinator answers `Done` before it exits.
+    pub fn run(&self) -> Result<usize> {
+        std::fs::create_dir_all(&self.runner.log_dir)?;
+        let kani_version = self.runner.kani_version()?;
+        let pull = Request::Pull { worker: self.name.clone() };
+        let mut pushed = 0;
+        let mut attempts = 0;
+        let mut connected = false;
+        loop {
+            let resp = match request(&self.addr, &pull) {
+                Ok(resp) => resp,
+                Err(err) if connected => return Err(err.wrap_err("Lost the coordinator")),
+                Err(err) => {
+                    attempts += 1;
+                    if attempts >= Self::CONNECT_ATTEMPTS {
+                        return Err(err);
+                    }
+                    std::thread::sleep(Duration::from_secs(1));
+                    continue;
+                }
+            };
+            connected = true;
+            match resp {
+                Response::Job { position, job } => {
+                    let _span = debug_span!("job", job.name).entered();
+                    let result = self.run_job(&job, &kani_version)?;
+                    let push = Request::Push {
+                        worker: self.name.clone(),
+                        position,
+                        kani_version: kani_version.clone(),
+                        kani_args: self.runner.kani_args.clone(),
+                        result,
+                    };
+                    let resp = request(&self.addr, &push)
+                        .wrap_err_with(|| format!("Lost the coordinator pushing {}", job.name))?;
+                    ensure!(matches!(resp, Response::Ack), "Unexpected response to push: {resp:?}");
+                    pushed += 1;
+                }
+                Response::Wait => std::thread::sleep(Duration::from_secs(1)),
+                Response::Done => break,
+                Response::Ack => bail!("Unexpected response to pull"),
+            }
+        }
+        Ok(pushed)
+    }
+
+    fn run_job(&self, job: &Job, kani_version: &str) -> Result<ProofResult> {
+        let Some(cache) = &self.cache else { return Ok(self.runner.run(job)) };
+        let kani_args = &self.runner.kani_args;
+        if let Some(entry) = cache.get(&job.hash, kani_version, kani_args)? {
+            return Ok(entry.into());
+        }
+        let result = self.runner.run(job);
+        cache.insert_result(&result, kani_version, kani_args)?;
+        Ok(result)
+    }
+}
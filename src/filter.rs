@@ -0,0 +1,55 @@
+//! Filter proofs in a JSON output.
+
+use crate::{ProofView, Result, diff::ProofDiff, read_proofs, write_json};
+use std::collections::HashSet;
+
+/// Only keep proofs that satisfy all given conditions. The JSON format is preserved.
+#[derive(clap::Args, Debug)]
+pub struct FilterArgs {
+    /// JSON file of proofs.
+    pub file: String,
+
+    /// Keep proofs whose name contains one of the strings.
+    #[arg(long)]
+    pub name: Vec<String>,
+
+    /// Keep proofs whose file path starts with one of the prefixes.
+    #[arg(long)]
+    pub path: Vec<String>,
+
+    /// Keep proofs in the rerun list of a JSON emitted by `diff`.
+    #[arg(long)]
+    pub rerun: Option<String>,
+
+    /// Path to write the filtered JSON. Print to stdout if not set.
+    #[arg(long)]
+    pub json: Option<String>,
+}
+
+impl FilterArgs {
+    pub fn run(&self) -> Result<()> {
+        let mut proofs = read_proofs(&self.file)?;
+        let rerun = self.rerun.as_deref().map(read_rerun).transpose()?;
+        let total = proofs.len();
+        proofs.retain(|p| self.keep(p, rerun.as_ref()));
+        info!(total, kept = proofs.len());
+        write_json(self.json.as_deref(), &proofs)
+    }
+
+    fn keep(&self, proof: &ProofView, rerun: Option<&HashSet<(String, String)>>) -> bool {
+        let name = self.name.is_empty() || self.name.iter().any(|s| proof.name.contains(&**s));
+        let path = self.path.is_empty() || self.path.iter().any(|s| proof.file.starts_with(&**s));
+        let rerun = rerun
+            .map(|set| set.contains(&(proof.file.to_owned(), proof.name.to_owned())))
+            .unwrap_or(true);
+        name && path && rerun
+    }
+}
+
+/// Read file and name pairs of proofs to rerun from a diff JSON.
+pub fn read_rerun(path: &str) -> Result<HashSet<(String, String)>> {
+    let _span = debug_span!("read_rerun", path).entered();
+    let file = std::fs::File::open(path)?;
+    let diff: ProofDiff = serde_json::from_reader(std::io::BufReader::new(file))?;
+    Ok(diff.rerun.into_iter().map(|p| (p.file, p.name)).collect())
+}
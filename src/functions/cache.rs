@@ -2,11 +2,22 @@
 //! If the data hasn't been available, generate one and insert it.
 //! The data is always behind a borrow through the `get_*` callbacks.
 
-use super::utils::{SourceCode, source_code_with};
+use super::{
+    hash::{hash_fn, set_explain, set_hash_algorithm},
+    utils::{SourceCode, portable_name, source_code_with},
+};
+use distributed_verification::{
+    cargo::ENV_STD_LIBRARY,
+    hash::{HashArgs, HashProfile},
+};
 use rustc_data_structures::fx::FxHashMap;
 use rustc_middle::ty::TyCtxt;
+use rustc_smir::rustc_internal::internal;
 use rustc_span::source_map::{SourceMap, get_source_map};
-use stable_mir::mir::{Body, mono::Instance};
+use stable_mir::{
+    DefId,
+    mir::{Body, mono::Instance},
+};
 use std::{cell::RefCell, cmp::Ordering, sync::Arc};
 
 thread_local! {
@@ -22,6 +33,13 @@ pub fn set_rustc_ctx(tcx: TyCtxt) {
     CACHE.with(|c| c.borrow_mut().rustc = Some(rustc));
 }
 
+/// Set how function hashes are computed. Must be called before any query.
+pub fn set_hash_args(args: HashArgs) {
+    set_hash_algorithm(args.hash_algorithm);
+    set_explain(args.explain_hash.is_some());
+    CACHE.with(|c| c.borrow_mut().hash_args = args);
+}
+
 pub fn clear_rustc_ctx() {
     CACHE.with(|c| c.borrow_mut().rustc = None);
 }
@@ -42,6 +60,22 @@ pub fn get_source_code(inst: &Instance) -> Option<SourceCode> {
     get_cache_func(inst, |cf| cf.src.clone())
 }
 
+/// Hash of a single function, which is computed once and shared between proofs.
+pub fn get_fn_hash(inst: &Instance) -> Option<String> {
+    get_cache_func(inst, |cf| cf.hash.clone())
+}
+
+/// Def path with crate name and stable crate id, e.g. `core[8d2b]::ptr::read`.
+///
+/// Unlike the index in stable_mir's DefId, this is the same across sessions and machines,
+/// as long as the crate is compiled with the same metadata.
+pub fn def_path(def_id: DefId) -> String {
+    get_cache(|cache| {
+        let tcx = cache.rustc.as_ref().expect("No rustc context.").tcx;
+        tcx.def_path_debug_str(internal(tcx, def_id))
+    })
+}
+
 pub fn cmp_callees(a: &Instance, b: &Instance) -> Ordering {
     get_cache(|cache| {
         cache.get_or_insert(a);
@@ -58,13 +92,14 @@ struct Cache {
     set: FxHashMap<Instance, Option<CacheFunction>>,
     rustc: Option<RustcCxt>,
     path_prefixes: PathPrefixes,
+    hash_args: HashArgs,
 }
 
 impl Cache {
     fn new() -> Self {
-        let (set, rustc) = Default::default();
+        let (set, rustc, hash_args) = Default::default();
         let path_prefixes = PathPrefixes::new();
-        Cache { set, rustc, path_prefixes }
+        Cache { set, rustc, path_prefixes, hash_args }
     }
 
     fn get_or_insert(&mut self, inst: &Instance) -> Option<&CacheFunction> {
@@ -74,8 +109,15 @@ impl Cache {
                 let body = inst.body()?;
                 let rustc = self.rustc.as_ref()?;
                 let prefix = self.path_prefixes.prefixes();
-                let src = source_code_with(inst, body.span, rustc.tcx, &rustc.src_map, prefix);
-                Some(CacheFunction { body, src })
+                let src = source_code_with(inst, body.span, rustc.tcx, &rustc.src_map, &prefix);
+                let hash = match self.hash_args.hash_profile {
+                    HashProfile::Exact => hash_fn(&src, &body, &self.hash_args),
+                    HashProfile::Portable => {
+                        let src = src.portable(portable_name(inst, rustc.tcx));
+                        hash_fn(&src, &body, &self.hash_args)
+                    }
+                };
+                Some(CacheFunction { body, src, hash })
             })
             .as_ref()
     }
@@ -89,11 +131,14 @@ struct RustcCxt {
 struct CacheFunction {
     body: Body,
     src: SourceCode,
+    hash: String,
 }
 
 struct PathPrefixes {
     pwd: String,
     sysroot: String,
+    /// Parent of verify-rust-std's library directory in `--std-library` mode.
+    std_library: Option<String>,
 }
 
 impl PathPrefixes {
@@ -104,10 +149,14 @@ impl PathPrefixes {
         let out = std::process::Command::new("rustc").arg("--print=sysroot").output().unwrap();
         let sysroot = std::str::from_utf8(&out.stdout).unwrap().trim();
         let sysroot = format!("{sysroot}/lib/rustlib/src/rust/");
-        PathPrefixes { pwd, sysroot }
+
+        let std_library = std::env::var(ENV_STD_LIBRARY).ok();
+        PathPrefixes { pwd, sysroot, std_library }
     }
 
-    fn prefixes(&self) -> [&str; 2] {
-        [&*self.pwd, &self.sysroot]
+    fn prefixes(&self) -> Vec<&str> {
+        // library path is checked first, since it may be under pwd
+        let std_library = self.std_library.as_deref();
+        std_library.into_iter().chain([&*self.pwd, &self.sysroot]).collect()
     }
 }
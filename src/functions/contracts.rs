@@ -0,0 +1,104 @@
+//! Contracts checked by `proof_for_contract` and assumed by `stub_verified`.
+//!
+//! kani's contract macros generate closures in the body of the target function, named
+//! by attributes like `#[kanitool::checked_with = "..."]` and `#[kanitool::replaced_with = "..."]`.
+//! kani verifies the check closure for `proof_for_contract`, and calls the replace closure
+//! in place of the target for `stub_verified`, so these closures are dependencies of the proof.
+//! They're only passed to `kani_register_contract`, so they're not in the call graph.
+
+use super::{kani::Reachable, kanitool_attrs, resolve::resolve_fn};
+use distributed_verification::attrs::{KaniAttrs, attr_value};
+use eyre::Result;
+use indexmap::IndexSet;
+use rustc_middle::ty::TyCtxt;
+use rustc_smir::rustc_internal::internal;
+use stable_mir::{
+    CrateDef,
+    mir::{VarDebugInfoContents, mono::Instance},
+    ty::{ClosureKind, RigidTy, TyKind},
+};
+
+/// Attributes naming closures that kani runs to check the contract of the target.
+pub const CHECK_CLOSURES: &[&str] = &["checked_with", "recursion_check"];
+
+/// Attributes naming closures that kani runs in place of the target under `stub_verified`.
+pub const REPLACE_CLOSURES: &[&str] = &["replaced_with"];
+
+/// Functions that contract attributes of a harness refer to.
+#[derive(Debug, Default)]
+pub struct ContractTargets {
+    /// Target of `proof_for_contract`.
+    pub contract: Option<Instance>,
+    /// Targets of `stub_verified`.
+    pub stub_verified: Vec<Instance>,
+}
+
+impl ContractTargets {
+    /// Resolve contract paths of the harness, and find the `proof_for_contract` target among
+    /// callees, and `stub_verified` targets among functions replaced by their contracts.
+    /// A target is left out with a warning if its path doesn't refer to exactly one function.
+    pub fn new(tcx: TyCtxt, harness: &Instance, attrs: &KaniAttrs, reachable: &Reachable) -> Self {
+        let find = |path: &String, instances: &IndexSet<Instance>| {
+            find_target(tcx, harness, path, instances)
+                .inspect_err(|err| warn!("Skip the contract: {err:#}"))
+                .ok()
+                .flatten()
+        };
+        let contract =
+            attrs.proof_for_contract.as_ref().and_then(|path| find(path, &reachable.callees));
+        let stub_verified =
+            attrs.stub_verified.iter().filter_map(|path| find(path, &reachable.verified)).collect();
+        ContractTargets { contract, stub_verified }
+    }
+}
+
+/// The instance that a path in `proof_for_contract` or `stub_verified` of the harness refers to.
+fn find_target(
+    tcx: TyCtxt,
+    harness: &Instance,
+    path: &str,
+    instances: &IndexSet<Instance>,
+) -> Result<Option<Instance>> {
+    let def = resolve_fn(tcx, internal(tcx, harness.def.def_id()), path)?;
+    let target = instances.iter().find(|inst| internal(tcx, inst.def.def_id()) == def).copied();
+    if target.is_none() {
+        warn!(path, "The contract target isn't reachable from the harness");
+    }
+    Ok(target)
+}
+
+/// Is the callee a closure defined in the body of the target, like ones generated for
+/// `requires`, `ensures` and `modifies`?
+pub fn is_contract_closure(target: &Instance, callee: &Instance) -> bool {
+    let name = callee.def.name();
+    name.strip_prefix(&*target.def.name()).is_some_and(|rest| rest.starts_with("::{closure"))
+}
+
+/// Closures in the body of the target that are named by its attributes in `names`,
+/// like [`CHECK_CLOSURES`] or [`REPLACE_CLOSURES`].
+pub fn contract_closures(target: &Instance, names: &[&str]) -> Vec<Instance> {
+    let Some(body) = target.body() else { return Vec::new() };
+    let locals: Vec<_> = kanitool_attrs(target)
+        .iter()
+        .filter_map(|attr| names.iter().find_map(|name| attr_value(attr.as_str(), name)))
+        .collect();
+    body.var_debug_info
+        .iter()
+        .filter(|info| locals.contains(&info.name))
+        .filter_map(|info| match &info.value {
+            VarDebugInfoContents::Place(place) if place.projection.is_empty() => {
+                body.local_decl(place.local)
+            }
+            _ => None,
+        })
+        .filter_map(|decl| match decl.ty.kind() {
+            // The closure body itself, rather than a shim for a weaker closure kind.
+            TyKind::RigidTy(RigidTy::Closure(def, args)) => {
+                Instance::resolve_closure(def, &args, ClosureKind::Fn)
+                    .inspect_err(|err| error!(?err, ?def, "Can't resolve the contract closure"))
+                    .ok()
+            }
+            _ => None,
+        })
+        .collect()
+}
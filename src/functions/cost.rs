@@ -0,0 +1,106 @@
+//! Estimate verification cost of a proof from MIR bodies of itself and its callees.
+
+use super::cache;
+use indexmap::IndexSet;
+use serde::Serialize;
+use stable_mir::{
+    CrateDef,
+    mir::{
+        Body, CastKind, MirVisitor, Place, ProjectionElem, Rvalue, Terminator, TerminatorKind,
+        mono::Instance,
+        visit::{Location, PlaceContext},
+    },
+    ty::{RigidTy, TyKind},
+};
+
+/// Estimated verification cost. Counts are summed over the proof and all its callees.
+#[derive(Debug, Default, Serialize)]
+pub struct Cost {
+    /// Count of basic blocks.
+    pub basic_blocks: usize,
+    /// Count of loop back-edges, i.e. jumps to a basic block not after the current one.
+    pub back_edges: usize,
+    /// Count of `kani::any` and `kani::any_where` calls.
+    pub kani_any: usize,
+    /// Unwind bound from `#[kani::unwind]` on the proof.
+    pub unwind: Option<u32>,
+    /// Count of raw pointer operations: dereferences, address-of, and pointer casts.
+    pub raw_ptr_ops: usize,
+    /// Weighted sum of the counts above, for schedulers to compare proofs.
+    pub score: u64,
+}
+
+impl Cost {
+    pub fn new(proof: &Instance, callees: &IndexSet<Instance>, unwind: Option<u32>) -> Self {
+        let mut cost = Cost { unwind, ..Default::default() };
+        for inst in std::iter::once(proof).chain(callees) {
+            cache::get_body(inst, |body| cost.add_body(body));
+        }
+        cost.score = cost.score();
+        cost
+    }
+
+    fn add_body(&mut self, body: &Body) {
+        self.basic_blocks += body.blocks.len();
+        for (idx, bb) in body.blocks.iter().enumerate() {
+            self.back_edges += bb.terminator.successors().into_iter().filter(|&s| s <= idx).count();
+        }
+        let mut visitor = CostVisitor { body, kani_any: 0, raw_ptr_ops: 0 };
+        visitor.visit_body(body);
+        self.kani_any += visitor.kani_any;
+        self.raw_ptr_ops += visitor.raw_ptr_ops;
+    }
+
+    /// Rough weights: each loop is assumed to be unwound `unwind` times (or once if
+    /// no unwind is given), and nondeterministic values and raw pointers make
+    /// the solver work harder than straight-line code.
+    fn score(&self) -> u64 {
+        let unwind = self.unwind.unwrap_or(1).max(1) as u64;
+        self.basic_blocks as u64
+            + 10 * self.back_edges as u64 * unwind
+            + 5 * self.kani_any as u64
+            + 2 * self.raw_ptr_ops as u64
+    }
+}
+
+struct CostVisitor<'a> {
+    body: &'a Body,
+    kani_any: usize,
+    raw_ptr_ops: usize,
+}
+
+impl MirVisitor for CostVisitor<'_> {
+    fn visit_terminator(&mut self, terminator: &Terminator, location: Location) {
+        if let TerminatorKind::Call { func, .. } = &terminator.kind
+            && let Ok(ty) = func.ty(self.body.locals())
+            && let TyKind::RigidTy(RigidTy::FnDef(def, _)) = ty.kind()
+        {
+            let name = def.name();
+            if name.ends_with("kani::any") || name.ends_with("kani::any_where") {
+                self.kani_any += 1;
+            }
+        }
+        self.super_terminator(terminator, location);
+    }
+
+    fn visit_rvalue(&mut self, rvalue: &Rvalue, location: Location) {
+        if matches!(
+            rvalue,
+            Rvalue::AddressOf(..) | Rvalue::Cast(CastKind::PtrToPtr | CastKind::Transmute, ..)
+        ) {
+            self.raw_ptr_ops += 1;
+        }
+        self.super_rvalue(rvalue, location);
+    }
+
+    fn visit_place(&mut self, place: &Place, ptx: PlaceContext, location: Location) {
+        // Only check the first deref, since the local type is known without projections.
+        if matches!(place.projection.first(), Some(ProjectionElem::Deref))
+            && let Some(local) = self.body.locals().get(place.local)
+            && matches!(local.ty.kind(), TyKind::RigidTy(RigidTy::RawPtr(..)))
+        {
+            self.raw_ptr_ops += 1;
+        }
+        self.super_place(place, ptx, location);
+    }
+}
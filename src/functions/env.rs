@@ -0,0 +1,44 @@
+//! Fingerprint of the environment that a verdict depends on besides the source:
+//! the toolchain, session options that change codegen, and the kani version.
+
+use super::hash::ContentHasher;
+use rustc_interface::util::rustc_version_str;
+use rustc_middle::ty::TyCtxt;
+
+/// Hash of the rustc version, target, options like overflow checks and cfg values,
+/// and kani versions from the kani path and `kani list`.
+pub fn env_fingerprint(tcx: TyCtxt, kani_version: Option<&str>, kani_list: Option<&str>) -> String {
+    let sess = tcx.sess;
+    let mut cfg: Vec<_> = sess
+        .psess
+        .config
+        .iter()
+        .map(|(name, value)| match value {
+            Some(value) => format!("{name}={value:?}"),
+            None => name.to_string(),
+        })
+        .collect();
+    cfg.sort();
+
+    let fields = [
+        ("rustc", rustc_version_str().unwrap_or("unknown").to_owned()),
+        ("target", sess.opts.target_triple.to_string()),
+        ("opt-level", format!("{:?}", sess.opts.optimize)),
+        ("overflow-checks", sess.overflow_checks().to_string()),
+        ("debug-assertions", sess.opts.debug_assertions.to_string()),
+        ("ub-checks", sess.ub_checks().to_string()),
+        ("panic", sess.panic_strategy().desc().to_owned()),
+        ("cfg", cfg.join(",")),
+        ("kani", kani_version.unwrap_or_default().to_owned()),
+        ("kani-list", kani_list.unwrap_or_default().to_owned()),
+    ];
+    let mut hasher = ContentHasher::new();
+    for (key, value) in &fields {
+        debug!(key, value, "env_fingerprint");
+        hasher.write_str(key);
+        hasher.write_str(value);
+    }
+    let hash = hasher.finish();
+    info!(hash, "env_fingerprint");
+    hash
+}
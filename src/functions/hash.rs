@@ -0,0 +1,537 @@
+//! Stable hash values of functions and proofs.
+//!
+//! Each function is hashed on its own, and a proof hash is built from the hash
+//! of the proof function and hashes of its callees and statics, so a changed proof
+//! hash can be traced back to the functions that changed.
+//!
+//! Values of constants used in a function body are part of the function hash,
+//! because a changed `const` doesn't change the source of functions using it.
+//! For the same reason, ADT definitions used by a proof are hashed on their own.
+//! Callees without a body are hashed from their identities.
+
+use super::utils::SourceCode;
+use distributed_verification::{
+    SerFunction,
+    hash::{HashAlgorithm, HashArgs, HashMode},
+};
+use eyre::{Context, Result};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_lexer::{TokenKind, tokenize};
+use rustc_stable_hash::{FromStableHash, SipHasher128Hash, StableHasher, hashers::SipHasher128};
+use sha2::{Digest, Sha256};
+use stable_mir::{
+    CrateDef, CrateItem,
+    mir::{
+        Body, ConstOperand, MirVisitor,
+        alloc::{AllocId, GlobalAlloc},
+        mono::StaticDef,
+        visit::Location,
+    },
+    ty::{Allocation, ConstantKind, MirConst, RigidTy, Ty, TyKind},
+};
+use std::{
+    cell::{Cell, RefCell},
+    fmt::Write,
+    hash::Hasher,
+    path::Path,
+};
+
+thread_local! {
+    static ALGORITHM: Cell<HashAlgorithm> = const { Cell::new(HashAlgorithm::Sip128) };
+    /// Inputs of each hash computed so far, keyed by the hash value. None if not explaining.
+    static EXPLAINED: RefCell<Option<FxHashMap<String, Vec<String>>>> = const { RefCell::new(None) };
+}
+
+/// Set the algorithm for all hashes. Must be called before any hash is computed.
+pub fn set_hash_algorithm(algorithm: HashAlgorithm) {
+    ALGORITHM.set(algorithm);
+}
+
+/// Record inputs of all hashes computed from now on for [`explain_hash`].
+pub fn set_explain(explain: bool) {
+    EXPLAINED.set(explain.then(FxHashMap::default));
+}
+
+struct Hash128(String);
+
+impl FromStableHash for Hash128 {
+    type Hash = SipHasher128Hash;
+
+    fn from(SipHasher128Hash([a, b]): SipHasher128Hash) -> Hash128 {
+        Hash128(format!("sip128:{a:016x}{b:016x}"))
+    }
+}
+
+/// A hasher in the algorithm set by [`set_hash_algorithm`].
+///
+/// Integers are written in little endian, and strings are suffixed with 0xff like
+/// `StableHasher`, so the input is encoded the same way for all algorithms.
+pub struct ContentHasher {
+    algorithm: Algorithm,
+    /// Inputs in order, one per line, if [`set_explain`] is on.
+    log: Option<Vec<String>>,
+}
+
+enum Algorithm {
+    Sip128(StableHasher<SipHasher128>),
+    Sha256(Sha256),
+}
+
+impl Default for ContentHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContentHasher {
+    pub fn new() -> Self {
+        let algorithm = match ALGORITHM.get() {
+            HashAlgorithm::Sip128 => Algorithm::Sip128(StableHasher::new()),
+            HashAlgorithm::Sha256 => Algorithm::Sha256(Sha256::new()),
+        };
+        let log = EXPLAINED.with_borrow(|explained| explained.as_ref().map(|_| Vec::new()));
+        ContentHasher { algorithm, log }
+    }
+
+    /// Fixed-width lowercase hex with an algorithm prefix, e.g. `sip128:` + 32 digits
+    /// or `sha256:` + 64 digits.
+    pub fn finish(self) -> String {
+        let hash = match self.algorithm {
+            Algorithm::Sip128(hasher) => {
+                let Hash128(hash) = hasher.finish();
+                hash
+            }
+            Algorithm::Sha256(hasher) => format!("sha256:{:x}", hasher.finalize()),
+        };
+        if let Some(log) = self.log {
+            EXPLAINED.with_borrow_mut(|explained| {
+                if let Some(explained) = explained {
+                    explained.insert(hash.clone(), log);
+                }
+            });
+        }
+        hash
+    }
+
+    fn log(&mut self, line: impl FnOnce() -> String) {
+        if let Some(log) = &mut self.log {
+            log.push(line());
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        match &mut self.algorithm {
+            Algorithm::Sip128(hasher) => hasher.write(bytes),
+            Algorithm::Sha256(hasher) => hasher.update(bytes),
+        }
+    }
+
+    pub fn write_str(&mut self, s: &str) {
+        // multiline strings are split into lines, so dumps diff well
+        self.log(|| {
+            if s.contains('\n') {
+                s.split('\n').fold("str".to_owned(), |acc, line| acc + "\n    | " + line)
+            } else {
+                format!("str {s:?}")
+            }
+        });
+        match &mut self.algorithm {
+            Algorithm::Sip128(hasher) => hasher.write_str(s),
+            Algorithm::Sha256(hasher) => {
+                hasher.update(s.as_bytes());
+                hasher.update([0xff]);
+            }
+        }
+    }
+
+    pub fn write_length_prefix(&mut self, len: usize) {
+        self.log(|| format!("len {len}"));
+        self.write(&(len as u64).to_le_bytes());
+    }
+
+    pub fn write_u16(&mut self, i: u16) {
+        self.log(|| format!("u16 {i}"));
+        self.write(&i.to_le_bytes());
+    }
+
+    pub fn write_u32(&mut self, i: u32) {
+        self.log(|| format!("u32 {i}"));
+        self.write(&i.to_le_bytes());
+    }
+
+    pub fn write_usize(&mut self, i: usize) {
+        self.log(|| format!("usize {i}"));
+        // the same on 32 and 64 bit platforms
+        self.write(&(i as u64).to_le_bytes());
+    }
+}
+
+/// Inputs of the hash and hashes it's built from, in the order of first appearance.
+///
+/// Each section starts with a hash value, followed by its inputs indented. Inputs that are
+/// hash values themselves get their own sections, unless their inputs are unknown.
+pub fn explain_hash(hash: &str) -> String {
+    EXPLAINED.with_borrow(|explained| {
+        let Some(explained) = explained else { return String::new() };
+        let mut out = String::new();
+        let mut queue = vec![hash];
+        let mut seen = FxHashSet::default();
+        while let Some(hash) = queue.pop() {
+            if !seen.insert(hash) {
+                continue;
+            }
+            let Some(log) = explained.get(hash) else { continue };
+            _ = writeln!(out, "{hash}");
+            for line in log {
+                _ = writeln!(out, "  {line}");
+            }
+            out.push('\n');
+            // depth first, in the order of appearance
+            let inputs =
+                log.iter().filter_map(|line| line.strip_prefix("str \"")?.strip_suffix('"'));
+            let inputs: Vec<_> = inputs.filter(|input| explained.contains_key(*input)).collect();
+            queue.extend(inputs.into_iter().rev());
+        }
+        out
+    })
+}
+
+/// Write [`explain_hash`] of each proof to `{dir}/{proof name}.txt`.
+/// Only proofs whose names contain one of `filters` are explained, or all if it's empty.
+pub fn write_explanations(dir: &str, filters: &[String], proofs: &[SerFunction]) -> Result<()> {
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {dir}"))?;
+    let chosen = |f: &&SerFunction| {
+        filters.is_empty() || filters.iter().any(|filter| f.func.name.contains(filter))
+    };
+    for proof in proofs.iter().filter(chosen) {
+        let path = Path::new(dir).join(format!("{}.txt", proof.func.name.replace("::", "-")));
+        std::fs::write(&path, explain_hash(&proof.hash))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Hash of a single function in the given mode, together with constants it uses.
+pub fn hash_fn(func: &SourceCode, body: &Body, args: &HashArgs) -> String {
+    let code = match args.hash_mode {
+        HashMode::Source => hash_source(func),
+        HashMode::Tokens => hash_tokens(func),
+        HashMode::Mir => hash_mir(func, body),
+    };
+    let mut hasher = ContentHasher::new();
+    hasher.write_str(&code);
+    write_consts(&mut hasher, body);
+    hasher.finish()
+}
+
+/// Hash of a single function from its source code.
+pub fn hash_source(func: &SourceCode) -> String {
+    let mut hasher = ContentHasher::new();
+    func.with_hasher(&mut hasher);
+    hasher.finish()
+}
+
+/// Like [`hash_source`], but source text and macro backtraces are hashed as tokens,
+/// so comments and formatting don't matter.
+fn hash_tokens(func: &SourceCode) -> String {
+    let mut hasher = ContentHasher::new();
+    hasher.write_str(&func.name);
+    hasher.write_str(&func.mangled_name);
+    hasher.write_str(&func.kind);
+    hasher.write_str(&func.file);
+    write_tokens(&mut hasher, &func.src);
+    hasher.write_length_prefix(func.macro_backtrace_len);
+    for m in &func.macro_backtrace {
+        write_tokens(&mut hasher, &m.callsite);
+        write_tokens(&mut hasher, &m.defsite);
+    }
+    hasher.finish()
+}
+
+fn write_tokens(hasher: &mut ContentHasher, src: &str) {
+    let tokens = tokens(src);
+    hasher.write_length_prefix(tokens.len());
+    tokens.iter().for_each(|token| hasher.write_str(token));
+}
+
+/// Token texts in the source, except whitespace and comments including doc comments.
+fn tokens(src: &str) -> Vec<&str> {
+    let mut pos = 0;
+    tokenize(src)
+        .filter_map(|token| {
+            let text = &src[pos..pos + token.len as usize];
+            pos += token.len as usize;
+            let trivia = matches!(
+                token.kind,
+                TokenKind::Whitespace
+                    | TokenKind::LineComment { .. }
+                    | TokenKind::BlockComment { .. }
+            );
+            (!trivia).then_some(text)
+        })
+        .collect()
+}
+
+/// Hash of a single function from its name, instance kind, and canonical MIR.
+/// File path and source text are left out, since they don't affect semantics.
+fn hash_mir(func: &SourceCode, body: &Body) -> String {
+    let mut hasher = ContentHasher::new();
+    hasher.write_str(&func.name);
+    hasher.write_str(&func.kind);
+    hasher.write_str(&canonical_mir(body));
+    hasher.finish()
+}
+
+/// MIR text of a body without debug info, i.e. local names.
+///
+/// stable_mir doesn't print spans except in closure types, and allocation ids are
+/// session-local, so both are renumbered by the order of appearance.
+fn canonical_mir(body: &Body) -> String {
+    let mut buf = Vec::new();
+    // the function name is hashed on its own
+    if let Err(err) = body.dump(&mut buf, "") {
+        error!(?err, "Failed to dump MIR");
+    }
+    let text = String::from_utf8_lossy(&buf);
+
+    let mut allocs = FxHashMap::default();
+    let mut closures = FxHashMap::default();
+    let mut out = String::with_capacity(text.len());
+    let mut line_out = String::new();
+    for line in text.lines().filter(|line| !line.trim_start().starts_with("debug ")) {
+        line_out.clear();
+        renumber_allocs(line, &mut allocs, &mut line_out);
+        renumber_closures(&line_out, &mut closures, &mut out);
+        out.push('\n');
+    }
+    out
+}
+
+/// Replace spans in closure and coroutine types, e.g. `{closure@src/lib.rs:4:17: 4:24}`,
+/// with `{closure#n}` where n is the order of the span's first appearance,
+/// so moving code around doesn't change the hash.
+fn renumber_closures(text: &str, closures: &mut FxHashMap<String, usize>, out: &mut String) {
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find('}') else { break };
+        match rest[1..end].split_once('@') {
+            // e.g. `closure`, `coroutine`, `async block`
+            Some((kind, span)) if kind.bytes().all(|b| b.is_ascii_lowercase() || b == b' ') => {
+                let len = closures.len();
+                let n = *closures.entry(span.to_owned()).or_insert(len);
+                _ = write!(out, "{{{kind}#{n}}}");
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+}
+
+/// Replace `alloc{id}` with `alloc{n}` where n is the order of the id's first appearance.
+fn renumber_allocs<'a>(line: &'a str, allocs: &mut FxHashMap<&'a str, usize>, out: &mut String) {
+    const ALLOC: &str = "alloc";
+    let mut rest = line;
+    while let Some(pos) = rest.find(ALLOC) {
+        let end = pos + ALLOC.len();
+        let digits = rest[end..].bytes().take_while(u8::is_ascii_digit).count();
+        let word_start =
+            rest[..pos].chars().next_back().is_none_or(|c| !c.is_alphanumeric() && c != '_');
+        out.push_str(&rest[..pos]);
+        if digits > 0 && word_start {
+            let len = allocs.len();
+            let n = *allocs.entry(&rest[pos..end + digits]).or_insert(len);
+            _ = write!(out, "{ALLOC}{n}");
+        } else {
+            // e.g. `alloc::vec::Vec`
+            out.push_str(&rest[pos..end + digits]);
+        }
+        rest = &rest[end + digits..];
+    }
+    out.push_str(rest);
+}
+
+/// Hash of a proof from its function hash, attributes, function hashes of callees,
+/// and hashes of statics, types, and body-less callees.
+pub fn hash_proof(
+    func_hash: &str,
+    attrs: &[String],
+    callee_hashes: &[&str],
+    static_hashes: &[&str],
+    type_hashes: &[&str],
+    opaque_hashes: &[&str],
+    contract_hashes: &[&str],
+) -> String {
+    let mut hasher = ContentHasher::new();
+    hasher.write_str(func_hash);
+    hasher.write_length_prefix(attrs.len());
+    attrs.iter().for_each(|attr| hasher.write_str(attr));
+    hasher.write_length_prefix(callee_hashes.len());
+    callee_hashes.iter().for_each(|hash| hasher.write_str(hash));
+    hasher.write_length_prefix(static_hashes.len());
+    static_hashes.iter().for_each(|hash| hasher.write_str(hash));
+    hasher.write_length_prefix(type_hashes.len());
+    type_hashes.iter().for_each(|hash| hasher.write_str(hash));
+    hasher.write_length_prefix(opaque_hashes.len());
+    opaque_hashes.iter().for_each(|hash| hasher.write_str(hash));
+    hasher.write_length_prefix(contract_hashes.len());
+    contract_hashes.iter().for_each(|hash| hasher.write_str(hash));
+    hasher.finish()
+}
+
+/// Hash of a contract from the target function name, its kanitool attributes,
+/// and hashes of contract closures.
+pub fn hash_contract(target_name: &str, attrs: &[String], closure_hashes: &[&str]) -> String {
+    let mut hasher = ContentHasher::new();
+    hasher.write_str(target_name);
+    hasher.write_length_prefix(attrs.len());
+    attrs.iter().for_each(|attr| hasher.write_str(attr));
+    hasher.write_length_prefix(closure_hashes.len());
+    closure_hashes.iter().for_each(|hash| hasher.write_str(hash));
+    hasher.finish()
+}
+
+/// Hash of a static from its name, type, and initializer value.
+pub fn hash_static(def: &StaticDef) -> String {
+    let mut hasher = ContentHasher::new();
+    hasher.write_str(&def.name());
+    hasher.write_str(&def.ty().to_string());
+    match initializer(def) {
+        Some(alloc) => write_allocation(&mut hasher, &alloc, 0),
+        None => hasher.write_str("no initializer"),
+    }
+    hasher.finish()
+}
+
+/// Value of a static, or None for foreign statics and thread locals.
+fn initializer(def: &StaticDef) -> Option<Allocation> {
+    // rustc panics when evaluating a foreign static
+    if CrateItem::from(*def).is_foreign_item() {
+        return None;
+    }
+    def.eval_initializer().inspect_err(|err| debug!(?def, ?err, "No initializer")).ok()
+}
+
+/// Proof hash mixed with the environment fingerprint.
+pub fn hash_with_env(proof_hash: &str, env: &str) -> String {
+    let mut hasher = ContentHasher::new();
+    hasher.write_str(proof_hash);
+    hasher.write_str(env);
+    hasher.finish()
+}
+
+/// Hash of a body-less callee from its instance kind, def path, and signature.
+pub fn hash_opaque(kind: &str, name: &str, sig: &str) -> String {
+    let mut hasher = ContentHasher::new();
+    hasher.write_str(kind);
+    hasher.write_str(name);
+    hasher.write_str(sig);
+    hasher.finish()
+}
+
+/// Hash of an ADT type from its name, variants, field names and types, and layout.
+/// The layout reflects `#[repr]`, which doesn't show in fields.
+pub fn hash_adt(ty: Ty) -> String {
+    let mut hasher = ContentHasher::new();
+    hasher.write_str(&ty.to_string());
+    if let TyKind::RigidTy(RigidTy::Adt(def, args)) = ty.kind() {
+        hasher.write_str(&format!("{:?}", def.kind()));
+        let variants = def.variants();
+        hasher.write_length_prefix(variants.len());
+        for variant in variants {
+            hasher.write_str(&variant.name());
+            let fields = variant.fields();
+            hasher.write_length_prefix(fields.len());
+            for field in fields {
+                hasher.write_str(&field.name);
+                hasher.write_str(&field.ty_with_args(&args).to_string());
+            }
+        }
+    }
+    match ty.layout() {
+        Ok(layout) => hasher.write_str(&format!("{:?}", layout.shape())),
+        // e.g. unsized types
+        Err(err) => {
+            debug!(?ty, ?err, "No layout");
+            hasher.write_str("no layout");
+        }
+    }
+    hasher.finish()
+}
+
+/// Write values of constants in the body in the order of appearance.
+fn write_consts(hasher: &mut ContentHasher, body: &Body) {
+    struct Consts(Vec<MirConst>);
+    impl MirVisitor for Consts {
+        fn visit_const_operand(&mut self, constant: &ConstOperand, location: Location) {
+            self.0.push(constant.const_.clone());
+            self.super_const_operand(constant, location);
+        }
+    }
+    let mut consts = Consts(Vec::new());
+    consts.visit_body(body);
+
+    let mut closures = FxHashMap::default();
+    hasher.write_length_prefix(consts.0.len());
+    for constant in &consts.0 {
+        match constant.kind() {
+            ConstantKind::Allocated(alloc) => write_allocation(hasher, alloc, 0),
+            ConstantKind::Unevaluated(uneval) => {
+                hasher.write_str(&uneval.def.name());
+                hasher.write_u32(uneval.promoted.unwrap_or(u32::MAX));
+            }
+            ConstantKind::Ty(_) | ConstantKind::Param(_) | ConstantKind::ZeroSized => {
+                // e.g. `<{closure@src/lib.rs:4:17: 4:24} as Fn<(u8,)>>::call`
+                let mut ty = String::new();
+                renumber_closures(&constant.ty().to_string(), &mut closures, &mut ty);
+                hasher.write_str(&ty);
+            }
+        }
+    }
+}
+
+/// Max depth to follow pointers in allocations.
+const MAX_ALLOC_DEPTH: usize = 8;
+
+/// Write bytes of an allocation and what its pointers point to.
+///
+/// Allocation ids are session-local, so pointees are written by name for functions,
+/// and by content for memory and statics.
+fn write_allocation(hasher: &mut ContentHasher, alloc: &Allocation, depth: usize) {
+    hasher.write_length_prefix(alloc.bytes.len());
+    // uninitialized bytes are out of u8 range
+    alloc.bytes.iter().for_each(|byte| hasher.write_u16(byte.map_or(u16::MAX, u16::from)));
+    hasher.write_length_prefix(alloc.provenance.ptrs.len());
+    for (offset, prov) in &alloc.provenance.ptrs {
+        hasher.write_usize(*offset);
+        write_alloc_id(hasher, prov.0, depth + 1);
+    }
+}
+
+fn write_alloc_id(hasher: &mut ContentHasher, id: AllocId, depth: usize) {
+    if depth > MAX_ALLOC_DEPTH {
+        hasher.write_str("...");
+        return;
+    }
+    match GlobalAlloc::from(id) {
+        GlobalAlloc::Function(inst) => hasher.write_str(&inst.name()),
+        // nested statics only live in the initializer of another static,
+        // so their values are written too
+        GlobalAlloc::Static(def) => {
+            hasher.write_str(&def.name());
+            if let Some(alloc) = initializer(&def) {
+                write_allocation(hasher, &alloc, depth);
+            }
+        }
+        GlobalAlloc::Memory(alloc) => write_allocation(hasher, &alloc, depth),
+        vtable @ GlobalAlloc::VTable(..) => match vtable.vtable_allocation() {
+            Some(id) => write_alloc_id(hasher, id, depth + 1),
+            None => hasher.write_str("vtable"),
+        },
+    }
+}
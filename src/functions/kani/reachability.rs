@@ -18,6 +18,8 @@
 //! kept with internal APIs.
 
 use super::coercion;
+use crate::functions::contracts::{REPLACE_CLOSURES, contract_closures};
+use crate::functions::stubs::{Stubbed, Stubs};
 use indexmap::IndexSet;
 use rustc_data_structures::fingerprint::Fingerprint;
 use rustc_data_structures::fx::FxHashSet;
@@ -575,27 +577,83 @@ struct Node(pub MonoItem);
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 struct CollectedNode(pub CollectedItem);
 
+/// Functions and statics reachable from a harness under its stubs.
+#[derive(Debug, Default)]
+pub struct Reachable {
+    /// Functions whose bodies are followed, including contract closures that kani runs.
+    pub callees: IndexSet<Instance>,
+    /// Statics, including ones used in initializers of other statics.
+    pub statics: IndexSet<StaticDef>,
+    /// Functions replaced by their contracts through `stub_verified`.
+    /// Their bodies aren't followed.
+    pub verified: IndexSet<Instance>,
+}
+
 impl CallGraph {
-    pub fn recursive_callees(&self, item: &MonoItem, callees: &mut IndexSet<Instance>) {
+    /// Collect functions and statics reachable from the item.
+    ///
+    /// Stubbed functions are not followed: their replacements are followed instead,
+    /// and functions replaced by their contracts are followed through the replace
+    /// closures in their bodies.
+    pub fn recursive_callees(
+        &self,
+        tcx: TyCtxt,
+        item: &MonoItem,
+        reachable: &mut Reachable,
+        stubs: &Stubs,
+    ) {
         let key = &Node(item.clone());
         let nodes = self.edges.get(key).unwrap_or_else(|| panic!("No {item:?} in the call graph."));
 
         for node in nodes {
             let item = &node.0.item;
             match item {
-                MonoItem::Fn(inst) => {
-                    if callees.insert(*inst) {
-                        // first insert the function instance
-                        self.recursive_callees(item, callees);
+                MonoItem::Fn(inst) => match stubs.get(inst) {
+                    Stubbed::No => {
+                        if reachable.callees.insert(*inst) {
+                            // first insert the function instance
+                            self.recursive_callees(tcx, item, reachable, stubs);
+                        }
+                    }
+                    Stubbed::Replaced(replacement) => {
+                        self.follow(tcx, replacement, reachable, stubs);
+                    }
+                    Stubbed::Verified => {
+                        if reachable.verified.insert(*inst) {
+                            for closure in contract_closures(inst, REPLACE_CLOSURES) {
+                                self.follow(tcx, closure, reachable, stubs);
+                            }
+                        }
+                    }
+                },
+                // Functions in initializers of statics are reachable too.
+                MonoItem::Static(def) => {
+                    if reachable.statics.insert(*def) {
+                        self.recursive_callees(tcx, item, reachable, stubs);
                     }
                 }
-                // TODO: only consider functions items.
-                // Functions may be in statics, but need testing to comfirm.
-                MonoItem::Static(_) => (),
-                _ => (),
+                MonoItem::GlobalAsm(_) => (),
             }
         }
     }
+
+    /// Collect the function and what's reachable from it, unless it's collected already.
+    ///
+    /// The function may be missing in the graph, like a generic replacement only
+    /// instantiated for a stub, or a contract closure only called by kani, so its
+    /// callees are collected in a graph of its own.
+    pub fn follow(&self, tcx: TyCtxt, inst: Instance, reachable: &mut Reachable, stubs: &Stubs) {
+        if !reachable.callees.insert(inst) {
+            return;
+        }
+        let item = MonoItem::Fn(inst);
+        if self.edges.contains_key(&Node(item.clone())) {
+            self.recursive_callees(tcx, &item, reachable, stubs);
+        } else {
+            let (_, graph) = collect_reachable_items(tcx, &[item.clone()]);
+            graph.recursive_callees(tcx, &item, reachable, stubs);
+        }
+    }
 }
 
 impl CallGraph {
@@ -1,24 +1,48 @@
+use distributed_verification::attrs::{KaniAttrs, PROOF_ATTRS, attr_name};
+use eyre::{Context, Result};
 use indexmap::IndexSet;
-use kani::{CallGraph, KANI_TOOL_ATTRS, collect_reachable_items};
+use kani::{CallGraph, Reachable, collect_reachable_items};
 use rustc_middle::ty::TyCtxt;
 use stable_mir::{
     CrateDef,
     crate_def::Attribute,
-    mir::mono::{Instance, MonoItem},
+    mir::mono::{Instance, MonoItem, StaticDef},
 };
 
 mod cache;
-pub use cache::{clear_rustc_ctx, set_rustc_ctx};
+pub use cache::{clear_rustc_ctx, set_hash_args, set_rustc_ctx};
+
+mod contracts;
+use contracts::ContractTargets;
+
+mod cost;
+
+mod env;
+pub use env::env_fingerprint;
+
+mod hash;
+pub use hash::write_explanations;
 
 mod kani;
 
 mod utils;
 pub use utils::vec_convertion;
 
+mod opaque;
+
+mod resolve;
+
+mod stubs;
+
+mod types;
+
 mod serialization;
 pub use serialization::SerFunction;
 
-pub fn analyze(tcx: TyCtxt) -> Vec<SerFunction> {
+/// Analyze proofs in the local crate. `env` is the environment fingerprint,
+/// which is mixed into proof hashes if `hash_env` is true.
+/// Fails if stubs of a proof can't be resolved, like kani does.
+pub fn analyze(tcx: TyCtxt, env: &str, hash_env: bool) -> Result<Vec<SerFunction>> {
     let local_items = stable_mir::all_local_items();
     let cap = local_items.len();
 
@@ -34,14 +58,36 @@ pub fn analyze(tcx: TyCtxt) -> Vec<SerFunction> {
     let (mono_items, callgraph) = collect_reachable_items(tcx, &entries);
 
     // Filter out non kanitool functions.
-    let mut proofs: Vec<_> = mono_items
+    let functions = mono_items
         .iter()
-        .filter_map(|f| Function::new(f, &callgraph, |x| !x.attrs.is_empty()))
-        .map(SerFunction::new)
+        .filter_map(|f| Function::new(tcx, f, &callgraph).transpose())
+        .collect::<Result<Vec<_>>>()?;
+    let mut proofs: Vec<_> = functions
+        .into_iter()
+        .filter_map(|f| {
+            // An unknown proof kind shouldn't abort the analysis of the whole crate.
+            SerFunction::new(f, env, hash_env)
+                .inspect_err(|err| error!(%err, "Skip the proof"))
+                .ok()
+        })
         .collect();
+    serialization::link_contract_proofs(&mut proofs);
     // Sort proofs by file path and source code.
     proofs.sort_by(|a, b| a.cmp_by_file_and_func(b));
-    proofs
+    Ok(proofs)
+}
+
+/// kanitool attributes on the function: proof, proof_for_contract, unwind, stub, requires, ...
+fn kanitool_attrs(inst: &Instance) -> Vec<Attribute> {
+    let mut attrs = inst.def.all_tool_attrs();
+    attrs.retain(|attr| attr_name(attr.as_str()).is_some());
+    attrs
+}
+
+/// Has `#[kanitool::proof]` or `#[kanitool::proof_for_contract]`?
+fn is_proof(attrs: &[Attribute]) -> bool {
+    let is_proof = |name: &str| PROOF_ATTRS.contains(&name);
+    attrs.iter().any(|attr| attr_name(attr.as_str()).is_some_and(is_proof))
 }
 
 /// A Rust funtion with its file source, attributes, and raw function content.
@@ -56,30 +102,52 @@ pub struct Function {
     /// Recursive fnction calls inside the body.
     /// The elements are sorted by file path and fn source code to keep hash value stable.
     callees: IndexSet<Instance>,
+
+    /// Statics reachable from the function, sorted by name.
+    statics: IndexSet<StaticDef>,
+
+    /// Callees without a body, like intrinsics and foreign functions, sorted by name.
+    opaque: IndexSet<Instance>,
+
+    /// Functions in `proof_for_contract` and `stub_verified`.
+    contracts: ContractTargets,
 }
 
 impl Function {
-    pub fn new(
-        item: &MonoItem,
-        callgraph: &CallGraph,
-        filter: impl FnOnce(&Self) -> bool,
-    ) -> Option<Self> {
+    /// Collect what the proof depends on, or None if the item isn't a proof with a body.
+    /// Fails if stubs of the proof can't be resolved.
+    pub fn new(tcx: TyCtxt, item: &MonoItem, callgraph: &CallGraph) -> Result<Option<Self>> {
         // Skip non fn items
         let &MonoItem::Fn(instance) = item else {
-            return None;
+            return Ok(None);
         };
 
-        // Skip if no body.
-        cache::get_body(&instance, |_| ())?;
-
-        // Only need kanitool attrs: proof, proof_for_contract, contract, ...
-        let attrs = KANI_TOOL_ATTRS.iter().flat_map(|v| instance.def.tool_attrs(v)).collect();
-
-        let mut callees = IndexSet::new();
-        callgraph.recursive_callees(item, &mut callees);
+        let attrs = kanitool_attrs(&instance);
+        // Skip if not a proof or no body.
+        if !is_proof(&attrs) || cache::get_body(&instance, |_| ()).is_none() {
+            return Ok(None);
+        }
+
+        let raw_attrs: Vec<_> = attrs.iter().map(|attr| attr.as_str().to_owned()).collect();
+        let kani_attrs = KaniAttrs::parse(&raw_attrs);
+        let stubs = stubs::Stubs::new(tcx, &instance, &kani_attrs)
+            .wrap_err_with(|| format!("Failed to resolve stubs of {}", instance.name()))?;
+
+        let mut reachable = Reachable::default();
+        callgraph.recursive_callees(tcx, item, &mut reachable, &stubs);
+        let contracts = ContractTargets::new(tcx, &instance, &kani_attrs, &reachable);
+        if let Some(target) = &contracts.contract {
+            for closure in contracts::contract_closures(target, contracts::CHECK_CLOSURES) {
+                callgraph.follow(tcx, closure, &mut reachable, &stubs);
+            }
+        }
+
+        let Reachable { mut callees, mut statics, .. } = reachable;
+        callees.retain(|callee| cache::get_body(callee, |_| ()).is_some());
         callees.sort_by(cache::cmp_callees);
+        statics.sort_by_cached_key(|def| def.name());
+        let opaque = opaque::collect_opaque(&instance, &callees);
 
-        let this = Function { instance, attrs, callees };
-        filter(&this).then_some(this)
+        Ok(Some(Function { instance, attrs, callees, statics, opaque, contracts }))
     }
 }
@@ -0,0 +1,52 @@
+//! Collect callees without a MIR body, e.g. intrinsics, foreign functions and virtual calls.
+//!
+//! They can't be hashed from source or MIR, so they're identified by instance kind,
+//! def path and signature, and a proof hash changes when they're added or removed.
+
+use super::cache;
+use indexmap::IndexSet;
+use stable_mir::{
+    mir::{Body, MirVisitor, Terminator, TerminatorKind, mono::Instance, visit::Location},
+    ty::{RigidTy, TyKind},
+};
+
+/// Body-less instances called from the proof or callees, sorted by name.
+/// Callees must have bodies.
+pub fn collect_opaque(proof: &Instance, callees: &IndexSet<Instance>) -> IndexSet<Instance> {
+    let mut called = IndexSet::new();
+    for inst in std::iter::once(proof).chain(callees) {
+        cache::get_body(inst, |body| CallVisitor { body, called: &mut called }.visit_body(body));
+    }
+    // Bodies are looked up after visiting, because the cache is borrowed while visiting.
+    let mut opaque: IndexSet<_> =
+        called.into_iter().filter(|inst| cache::get_body(inst, |_| ()).is_none()).collect();
+    opaque.sort_by_cached_key(|inst| (inst.name(), format!("{:?}", inst.kind)));
+    opaque
+}
+
+struct CallVisitor<'a> {
+    body: &'a Body,
+    called: &'a mut IndexSet<Instance>,
+}
+
+impl MirVisitor for CallVisitor<'_> {
+    fn visit_terminator(&mut self, terminator: &Terminator, location: Location) {
+        if let TerminatorKind::Call { func, .. } = &terminator.kind
+            && let Ok(ty) = func.ty(self.body.locals())
+            && let TyKind::RigidTy(RigidTy::FnDef(def, args)) = ty.kind()
+            && let Ok(inst) = Instance::resolve(def, &args)
+        {
+            self.called.insert(inst);
+        }
+        self.super_terminator(terminator, location);
+    }
+}
+
+/// Signature of an instance, e.g. `extern "C" fn(*const u8, usize) -> i32`.
+pub fn signature(inst: &Instance) -> String {
+    let Some(sig) = inst.ty().kind().fn_sig() else { return String::new() };
+    let sig = sig.skip_binder();
+    let inputs: Vec<_> = sig.inputs().iter().map(|ty| ty.to_string()).collect();
+    let variadic = if sig.c_variadic { ", ..." } else { "" };
+    format!("{:?} fn({}{variadic}) -> {}", sig.abi, inputs.join(", "), sig.output())
+}
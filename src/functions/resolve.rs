@@ -0,0 +1,260 @@
+//! Resolve paths in kanitool attributes to functions, as kani does for stubs and contracts.
+//!
+//! A path is resolved from the module of the harness: through items and `use` imports in
+//! the module, `crate`, `self` and `super`, the standard prelude, or crate names.
+//! `Type::method` is looked up in inherent impls of the type, and `<Type as Trait>::method`
+//! in the impl of the trait for the type, so two functions with the same name are never
+//! confused. Inherent methods of primitive types, like `u8::unchecked_add` or
+//! `<*const T>::add`, are looked up in their impls in `core`.
+
+use eyre::{Context, Result};
+use rustc_hir::{Mutability, PrimTy};
+use rustc_hir::{
+    def::{DefKind, Namespace, Res},
+    def_id::{DefId, LOCAL_CRATE},
+};
+use rustc_middle::ty::{self, TyCtxt, fast_reject::SimplifiedType};
+use rustc_span::Symbol;
+
+/// Resolve the path written in an attribute of the harness to a function.
+pub fn resolve_fn(tcx: TyCtxt, harness: DefId, path: &str) -> Result<DefId> {
+    let module = tcx.parent_module_from_def_id(harness.expect_local()).to_def_id();
+    let def = match qualified(path) {
+        Some((ty, Some(trait_path), name)) => resolve_trait_fn(tcx, module, ty, trait_path, name),
+        // `<Type>::method` is the same as `Type::method`
+        Some((ty, None, name)) => resolve_inherent_fn(tcx, module, ty, name),
+        None => match path.trim().rsplit_once("::") {
+            Some((ty, name)) => resolve_inherent_fn(tcx, module, ty, name),
+            None => resolve_path(tcx, module, path, Namespace::ValueNS),
+        },
+    }
+    .wrap_err_with(|| format!("Can't resolve `{path}`"))?;
+    ensure!(
+        matches!(tcx.def_kind(def), DefKind::Fn | DefKind::AssocFn),
+        "`{path}` resolves to `{}`, which isn't a function",
+        tcx.def_path_str(def)
+    );
+    Ok(def)
+}
+
+/// Split `<Type as Trait>::method` into the type, the trait and the method.
+fn qualified(path: &str) -> Option<(&str, Option<&str>, &str)> {
+    let path = path.trim().strip_prefix('<')?;
+    let end = find_top_level(path, ">")?;
+    let name = path[end + 1..].trim().strip_prefix("::")?.trim();
+    let inner = &path[..end];
+    Some(match find_top_level(inner, " as ") {
+        Some(i) => (inner[..i].trim(), Some(inner[i + 4..].trim()), name),
+        None => (inner.trim(), None, name),
+    })
+}
+
+/// Position of the first `pat` outside brackets.
+fn find_top_level(s: &str, pat: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        if depth == 0 && s[i..].starts_with(pat) {
+            return Some(i);
+        }
+        match c {
+            '<' | '[' | '(' => depth += 1,
+            '>' | ']' | ')' => depth -= 1,
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Resolve `Type::method` through the path of the type, or inherent impls of the type
+/// if it's a primitive type.
+fn resolve_inherent_fn(tcx: TyCtxt, module: DefId, ty: &str, name: &str) -> Result<DefId> {
+    resolve_path(tcx, module, &format!("{ty}::{name}"), Namespace::ValueNS)
+        .or_else(|err| resolve_primitive_fn(tcx, ty, name).unwrap_or(Err(err)))
+}
+
+/// Resolve a method in inherent impls of a primitive type, or None if the type isn't one.
+fn resolve_primitive_fn(tcx: TyCtxt, ty: &str, name: &str) -> Option<Result<DefId>> {
+    let simplified = simplify_primitive(ty)?;
+    let impls = tcx.incoherent_impls(simplified);
+    let fns = impls.iter().flat_map(|&imp| assoc_named(tcx, imp, name));
+    Some(unique(tcx, fns, || format!("`{name}` in inherent impls of `{ty}`")))
+}
+
+/// The simplified type of a primitive type written in a path, like `u8`, `[T]`, `[T; N]`
+/// or `*const T`, which impls of the type are keyed by.
+fn simplify_primitive(ty: &str) -> Option<SimplifiedType> {
+    let ty = ty.trim();
+    if let Some(inner) = ty.strip_prefix('[').and_then(|ty| ty.strip_suffix(']')) {
+        let is_array = find_top_level(inner, ";").is_some();
+        return Some(if is_array { SimplifiedType::Array } else { SimplifiedType::Slice });
+    }
+    if let Some(pointee) = ty.strip_prefix('*') {
+        let pointee = pointee.trim_start();
+        let mutbl = if pointee.starts_with("mut ") { Mutability::Mut } else { Mutability::Not };
+        return Some(SimplifiedType::Ptr(mutbl));
+    }
+    Some(match PrimTy::from_name(Symbol::intern(ty))? {
+        PrimTy::Int(int) => SimplifiedType::Int(ty::int_ty(int)),
+        PrimTy::Uint(uint) => SimplifiedType::Uint(ty::uint_ty(uint)),
+        PrimTy::Float(float) => SimplifiedType::Float(ty::float_ty(float)),
+        PrimTy::Str => SimplifiedType::Str,
+        PrimTy::Bool => SimplifiedType::Bool,
+        PrimTy::Char => SimplifiedType::Char,
+    })
+}
+
+/// Resolve a plain path like `a::b::c`. The last segment is looked up in the namespace.
+fn resolve_path(tcx: TyCtxt, module: DefId, path: &str, ns: Namespace) -> Result<DefId> {
+    let global = path.trim_start().starts_with("::");
+    let segments = segments(path);
+    let Some((first, rest)) = segments.split_first() else { bail!("Empty path") };
+
+    let (mut current, rest) = match first.as_str() {
+        _ if global => (find_crate(tcx, first)?, rest),
+        "crate" => (LOCAL_CRATE.as_def_id(), rest),
+        "self" => (module, rest),
+        "super" => (module, &segments[..]),
+        name => {
+            let ns = if rest.is_empty() { ns } else { Namespace::TypeNS };
+            let def = find_child(tcx, module, name, ns).or_else(|| find_in_prelude(tcx, name, ns));
+            (def.map_or_else(|| find_crate(tcx, name), Ok)?, rest)
+        }
+    };
+    for (i, name) in rest.iter().enumerate() {
+        let ns = if i + 1 == rest.len() { ns } else { Namespace::TypeNS };
+        current = match name.as_str() {
+            "super" => tcx
+                .opt_parent(current)
+                .filter(|&parent| tcx.def_kind(parent) == DefKind::Mod)
+                .ok_or_else(|| eyre!("`{}` has no parent module", tcx.def_path_str(current)))?,
+            name => resolve_child(tcx, current, name, ns)?,
+        };
+    }
+    Ok(current)
+}
+
+/// Resolve a name in a module, type or trait.
+fn resolve_child(tcx: TyCtxt, parent: DefId, name: &str, ns: Namespace) -> Result<DefId> {
+    let path = || tcx.def_path_str(parent);
+    match tcx.def_kind(parent) {
+        DefKind::Mod => {
+            find_child(tcx, parent, name, ns).ok_or_else(|| eyre!("No `{name}` in `{}`", path()))
+        }
+        DefKind::Struct | DefKind::Enum | DefKind::Union | DefKind::TyAlias => {
+            let adt = adt_of(tcx, parent).ok_or_else(|| eyre!("`{}` isn't an ADT", path()))?;
+            let impls = tcx.inherent_impls(adt);
+            let fns = impls.iter().flat_map(|&imp| assoc_named(tcx, imp, name));
+            unique(tcx, fns, || format!("`{name}` in inherent impls of `{}`", path()))
+        }
+        DefKind::Trait => {
+            unique(tcx, assoc_named(tcx, parent, name), || format!("`{name}` in `{}`", path()))
+        }
+        kind => bail!("Can't look up `{name}` in {kind:?} `{}`", path()),
+    }
+}
+
+/// Resolve `<Type as Trait>::method` to the method in the impl, or the default method
+/// of the trait if the impl doesn't override it.
+fn resolve_trait_fn(
+    tcx: TyCtxt,
+    module: DefId,
+    ty: &str,
+    trait_path: &str,
+    name: &str,
+) -> Result<DefId> {
+    let trait_def = resolve_path(tcx, module, trait_path, Namespace::TypeNS)?;
+    ensure!(tcx.def_kind(trait_def) == DefKind::Trait, "`{trait_path}` isn't a trait");
+    // Primitive types can't be resolved as paths, so they're compared by name.
+    let adt =
+        resolve_path(tcx, module, ty, Namespace::TypeNS).ok().and_then(|def| adt_of(tcx, def));
+    let ty_name = segments(ty).join("::");
+    let impls = tcx.all_impls(trait_def).filter(|&imp| {
+        let self_ty = tcx.type_of(imp).instantiate_identity();
+        match adt {
+            Some(adt) => self_ty.ty_adt_def().is_some_and(|def| def.did() == adt),
+            None => segments(&self_ty.to_string()).join("::") == ty_name,
+        }
+    });
+    let imp = unique(tcx, impls, || format!("impl of `{trait_path}` for `{ty}`"))?;
+    let mut fns = assoc_named(tcx, imp, name).chain(assoc_named(tcx, trait_def, name));
+    fns.next().ok_or_else(|| eyre!("No `{name}` in `{trait_path}`"))
+}
+
+/// A child of a module by name in the namespace, including imports.
+fn find_child(tcx: TyCtxt, module: DefId, name: &str, ns: Namespace) -> Option<DefId> {
+    let children = match module.as_local() {
+        Some(local) => tcx.module_children_local(local),
+        None => tcx.module_children(module),
+    };
+    children.iter().filter(|child| child.ident.as_str() == name).find_map(|child| match child.res {
+        Res::Def(kind, def) if kind.ns() == Some(ns) => Some(def),
+        _ => None,
+    })
+}
+
+/// A name in the standard prelude of the edition, like `Clone` or `Vec`.
+fn find_in_prelude(tcx: TyCtxt, name: &str, ns: Namespace) -> Option<DefId> {
+    let root = find_crate(tcx, "std").or_else(|_| find_crate(tcx, "core")).ok()?;
+    let prelude = find_child(tcx, root, "prelude", Namespace::TypeNS)?;
+    let edition = format!("rust_{}", tcx.sess.edition());
+    let prelude = find_child(tcx, prelude, &edition, Namespace::TypeNS)?;
+    find_child(tcx, prelude, name, ns)
+}
+
+/// The root module of a crate by name.
+fn find_crate(tcx: TyCtxt, name: &str) -> Result<DefId> {
+    let krate = tcx.crates(()).iter().find(|&&krate| tcx.crate_name(krate).as_str() == name);
+    krate
+        .map(|krate| krate.as_def_id())
+        .ok_or_else(|| eyre!("No `{name}` in the module of the harness or crates"))
+}
+
+/// The ADT of a struct, enum, union, or type alias of them.
+fn adt_of(tcx: TyCtxt, def: DefId) -> Option<DefId> {
+    match tcx.def_kind(def) {
+        DefKind::Struct | DefKind::Enum | DefKind::Union => Some(def),
+        DefKind::TyAlias => {
+            tcx.type_of(def).instantiate_identity().ty_adt_def().map(|adt| adt.did())
+        }
+        _ => None,
+    }
+}
+
+/// Associated items of an impl or trait with the name.
+fn assoc_named<'tcx>(tcx: TyCtxt<'tcx>, parent: DefId, name: &str) -> impl Iterator<Item = DefId> {
+    let items = tcx.associated_item_def_ids(parent).iter().copied();
+    items.filter(move |&def| tcx.item_name(def).as_str() == name)
+}
+
+/// The only definition, or an error naming all candidates.
+fn unique(
+    tcx: TyCtxt,
+    mut defs: impl Iterator<Item = DefId>,
+    what: impl Fn() -> String,
+) -> Result<DefId> {
+    let def = defs.next().ok_or_else(|| eyre!("Can't find {}", what()))?;
+    let others: Vec<_> = defs.map(|def| tcx.def_path_str(def)).collect();
+    ensure!(
+        others.is_empty(),
+        "{} is ambiguous: `{}` and {others:?}",
+        what(),
+        tcx.def_path_str(def)
+    );
+    Ok(def)
+}
+
+/// Path segments without generic arguments.
+fn segments(path: &str) -> Vec<String> {
+    let mut depth = 0;
+    let mut without_args = String::with_capacity(path.len());
+    for c in path.chars() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            _ if depth == 0 => without_args.push(c),
+            _ => (),
+        }
+    }
+    let segments = without_args.split("::").map(str::trim).filter(|s| !s.is_empty());
+    segments.map(str::to_owned).collect()
+}
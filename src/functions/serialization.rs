@@ -1,49 +1,127 @@
-use super::{cache, utils::SourceCode};
-use rustc_stable_hash::{FromStableHash, SipHasher128Hash, StableHasher, hashers::SipHasher128};
+use super::{
+    cache,
+    contracts::is_contract_closure,
+    cost::Cost,
+    hash::{
+        hash_adt, hash_contract, hash_opaque, hash_proof, hash_source, hash_static, hash_with_env,
+    },
+    kanitool_attrs,
+    opaque::signature,
+    types::collect_adts,
+    utils::SourceCode,
+};
+use distributed_verification::attrs::KaniAttrs;
+use eyre::Result;
+use indexmap::IndexSet;
 use serde::Serialize;
-use stable_mir::{CrateDef, mir::mono::Instance};
-use std::{cmp::Ordering, hash::Hasher};
+use stable_mir::{
+    CrateDef,
+    mir::mono::{Instance, StaticDef},
+    ty::Ty,
+};
+use std::{cmp::Ordering, collections::HashMap};
 
 /// A kani proof with its file source, attributes, and raw function content.
 #[derive(Debug, Serialize)]
 pub struct SerFunction {
     hash: String,
-    /// DefId in stable_mir.
+    /// Def path with crate name and stable crate id, e.g. `core[8d2b]::ptr::read`.
     def_id: String,
     /// Attributes are attached the function, but it seems that attributes
     /// and function must be separated to query.
     attrs: Vec<String>,
+    /// kanitool attributes parsed from `attrs`.
+    kani_attrs: KaniAttrs,
     /// Proof kind
     kind: Kind,
     /// Raw function string, including name, signature, and body.
     func: SourceCode,
+    /// Hash of the proof function alone.
+    func_hash: String,
     /// Count of callees.
     callees_len: usize,
+    /// Estimated verification cost from MIR.
+    cost: Cost,
     /// Recursive function calls inside the proof.
     callees: Vec<Callee>,
+    /// Statics reachable from the proof.
+    statics: Vec<Static>,
+    /// ADTs used by the proof and callees.
+    types: Vec<TypeDef>,
+    /// Callees without a body, like intrinsics and foreign functions.
+    opaque: Vec<Opaque>,
+    /// The contract checked by `proof_for_contract`.
+    contract: Option<Contract>,
+    /// Contracts of callees replaced through `stub_verified`.
+    stub_verified: Vec<Contract>,
+    /// Environment fingerprint.
+    env: String,
 }
 
 impl SerFunction {
-    pub fn new(fun: super::Function) -> Self {
+    pub fn new(fun: super::Function, env: &str, hash_env: bool) -> Result<Self> {
         let inst = fun.instance;
         let def_id = format_def_id(&inst);
         let attrs: Vec<_> = fun.attrs.iter().map(|a| a.as_str().to_owned()).collect();
-        let kind = Kind::new(&attrs);
+        let kani_attrs = KaniAttrs::parse(&attrs);
         // Though this is from body span, fn name and signature are included.
         let func = cache::get_source_code(&inst).unwrap_or_default();
+        let kind = Kind::new(&kani_attrs, &attrs, &func, &fun.callees)?;
+        let func_hash = cache::get_fn_hash(&inst).unwrap_or_else(|| hash_source(&func));
         let callees: Vec<_> = fun.callees.iter().map(Callee::new).collect();
         let callees_len = callees.len();
+        let cost = Cost::new(&inst, &fun.callees, kani_attrs.unwind);
 
-        // Hash
-        let mut hasher = StableHasher::<SipHasher128>::new();
-        func.with_hasher(&mut hasher);
-        hasher.write_length_prefix(attrs.len());
-        attrs.iter().for_each(|attr| hasher.write_str(attr));
-        hasher.write_length_prefix(callees_len);
-        callees.iter().for_each(|callee| callee.func.with_hasher(&mut hasher));
-        let Hash128(hash) = hasher.finish();
+        let statics: Vec<_> = fun.statics.iter().map(Static::new).collect();
+        let types: Vec<_> =
+            collect_adts(&inst, &fun.callees).into_iter().map(TypeDef::new).collect();
+        let opaque: Vec<_> = fun.opaque.iter().map(Opaque::new).collect();
 
-        SerFunction { hash, def_id, attrs, kind, func, callees_len, callees }
+        let callee_hashes: Vec<_> = callees.iter().map(|c| &*c.hash).collect();
+        let static_hashes: Vec<_> = statics.iter().map(|s| &*s.hash).collect();
+        let type_hashes: Vec<_> = types.iter().map(|t| &*t.hash).collect();
+        let opaque_hashes: Vec<_> = opaque.iter().map(|o| &*o.hash).collect();
+
+        let contract = fun.contracts.contract.map(|target| Contract::new(&target, &fun.callees));
+        let stub_verified: Vec<_> = fun
+            .contracts
+            .stub_verified
+            .iter()
+            .map(|target| Contract::new(target, &fun.callees))
+            .collect();
+        let contract_hashes: Vec<_> =
+            contract.iter().chain(&stub_verified).map(|c| &*c.hash).collect();
+
+        let hash = hash_proof(
+            &func_hash,
+            &attrs,
+            &callee_hashes,
+            &static_hashes,
+            &type_hashes,
+            &opaque_hashes,
+            &contract_hashes,
+        );
+        let hash = if hash_env { hash_with_env(&hash, env) } else { hash };
+        let env = env.to_owned();
+
+        Ok(SerFunction {
+            hash,
+            def_id,
+            attrs,
+            kani_attrs,
+            kind,
+            func,
+            func_hash,
+            callees_len,
+            cost,
+            callees,
+            statics,
+            types,
+            opaque,
+            contract,
+            stub_verified,
+            env,
+        })
     }
 
     /// Compare by file and func string.
@@ -52,58 +130,184 @@ impl SerFunction {
     }
 }
 
-// ************* hash *************
-struct Hash128(String);
-
-impl FromStableHash for Hash128 {
-    type Hash = SipHasher128Hash;
-
-    fn from(SipHasher128Hash([a, b]): SipHasher128Hash) -> Hash128 {
-        Hash128(format!("{a}{b}"))
-    }
-}
-// ************* hash *************
-
 fn format_def_id(inst: &Instance) -> String {
-    format!("{:?}", inst.def.def_id())
+    cache::def_path(inst.def.def_id())
 }
 
 #[derive(Debug, Serialize)]
 pub struct Callee {
     def_id: String,
     func: SourceCode,
+    /// Hash of the callee function alone.
+    hash: String,
 }
 
 impl Callee {
     fn new(inst: &Instance) -> Self {
         let def_id = format_def_id(inst);
         let func = cache::get_source_code(inst).unwrap_or_default();
-        Callee { def_id, func }
+        let hash = cache::get_fn_hash(inst).unwrap_or_else(|| hash_source(&func));
+        Callee { def_id, func, hash }
     }
 }
 
-/// kani proof kind
+/// A static that a proof depends on.
+#[derive(Debug, Serialize)]
+pub struct Static {
+    name: String,
+    /// Type of the static.
+    ty: String,
+    /// Hash of the name, type, and initializer value.
+    hash: String,
+}
+
+impl Static {
+    fn new(def: &StaticDef) -> Self {
+        Static { name: def.name(), ty: def.ty().to_string(), hash: hash_static(def) }
+    }
+}
+
+/// An ADT that a proof uses.
 #[derive(Debug, Serialize)]
+pub struct TypeDef {
+    /// Type name with generic arguments.
+    name: String,
+    /// Hash of the definition and layout.
+    hash: String,
+}
+
+impl TypeDef {
+    fn new(ty: Ty) -> Self {
+        TypeDef { name: ty.to_string(), hash: hash_adt(ty) }
+    }
+}
+
+/// A callee without a body, identified by instance kind, def path, and signature.
+#[derive(Debug, Serialize)]
+pub struct Opaque {
+    /// Instance kind, e.g. `Intrinsic`, `Virtual`, or `Item` for foreign functions.
+    kind: String,
+    /// Def path with generic arguments.
+    name: String,
+    /// Function signature.
+    sig: String,
+    /// Hash of the kind, name, and signature.
+    hash: String,
+}
+
+impl Opaque {
+    fn new(inst: &Instance) -> Self {
+        let kind = format!("{:?}", inst.kind);
+        let name = inst.name();
+        let sig = signature(inst);
+        let hash = hash_opaque(&kind, &name, &sig);
+        Opaque { kind, name, sig, hash }
+    }
+}
+
+/// The contract of a function, checked by `proof_for_contract` or assumed by `stub_verified`.
+#[derive(Debug, Serialize)]
+pub struct Contract {
+    /// Def path of the function with the contract.
+    target: String,
+    /// Function name with generic arguments.
+    name: String,
+    /// Clauses of `requires` on the target.
+    requires: Vec<String>,
+    /// Clauses of `ensures` on the target.
+    ensures: Vec<String>,
+    /// Clauses of `modifies` on the target.
+    modifies: Vec<String>,
+    /// Closures generated for the contract in the body of the target.
+    closures: Vec<String>,
+    /// Hash of the target name, its kanitool attributes, and the closures, but not the body
+    /// of the target, which kani doesn't run for `stub_verified`.
+    hash: String,
+    /// Proofs checking the contract. Only filled for `stub_verified`.
+    proofs: Vec<String>,
+}
+
+impl Contract {
+    fn new(target: &Instance, callees: &IndexSet<Instance>) -> Self {
+        let attrs: Vec<_> = kanitool_attrs(target).iter().map(|a| a.as_str().to_owned()).collect();
+        let KaniAttrs { requires, ensures, modifies, .. } = KaniAttrs::parse(&attrs);
+        let closures: Vec<_> =
+            callees.iter().filter(|c| is_contract_closure(target, c)).map(Callee::new).collect();
+        let closure_hashes: Vec<_> = closures.iter().map(|c| &*c.hash).collect();
+        let name = target.name();
+        let hash = hash_contract(&name, &attrs, &closure_hashes);
+        Contract {
+            target: format_def_id(target),
+            name,
+            requires,
+            ensures,
+            modifies,
+            closures: closures.into_iter().map(|c| c.func.name).collect(),
+            hash,
+            proofs: Vec::new(),
+        }
+    }
+}
+
+/// Link contracts in `stub_verified` to proofs checking them.
+pub fn link_contract_proofs(proofs: &mut [SerFunction]) {
+    let mut checked = HashMap::<String, Vec<String>>::new();
+    for proof in &*proofs {
+        if let Some(contract) = &proof.contract {
+            checked.entry(contract.target.clone()).or_default().push(proof.func.name.clone());
+        }
+    }
+    for proof in proofs {
+        for contract in &mut proof.stub_verified {
+            contract.proofs = checked.get(&contract.target).cloned().unwrap_or_default();
+            if contract.proofs.is_empty() {
+                warn!(proof = %proof.func.name, target = %contract.target, "No proof checks the contract");
+            }
+        }
+    }
+}
+
+/// kani proof kind
+#[derive(Debug, Serialize, Clone, Copy)]
 pub enum Kind {
     /// `#[kani::proof]` (actually `kanitool::proof`)
     Standard,
     /// `#[kani::proof_for_contract]` (actually `kanitool::proof_for_contract`)
     Contract,
+    /// `#[kani::proof]` reaching loops with `#[kani::loop_invariant]`.
+    LoopContract,
+    /// `#[kani::proof]` generated by a macro.
+    Generated,
 }
 
 impl Kind {
-    /// ## Panic
-    ///
-    /// The given attributes must contain one of the proof kind macro.
-    fn new(attrs: &[String]) -> Self {
-        for attr in attrs {
-            if attr.contains("kanitool::proof_for_contract") {
-                return Kind::Contract;
-            } else if attr.contains("kanitool::proof") {
-                return Kind::Standard;
-            }
+    /// Detect the proof kind from kanitool attributes, callees and the span of the proof.
+    /// Fails if neither `kanitool::proof` nor `kanitool::proof_for_contract` is found.
+    fn new(
+        kani_attrs: &KaniAttrs,
+        attrs: &[String],
+        func: &SourceCode,
+        callees: &IndexSet<Instance>,
+    ) -> Result<Self> {
+        if kani_attrs.proof_for_contract.is_some() {
+            return Ok(Kind::Contract);
         }
-        panic!("{attrs:?} doesn't contain a proof kind.")
+        ensure!(
+            kani_attrs.proof,
+            "Unknown proof kind of {}: neither `kanitool::proof` nor `kanitool::proof_for_contract` \
+             is found in {attrs:?}",
+            func.name
+        );
+        // kani registers each loop contract through a call to `kani_register_loop_contract`
+        let has_loop_contracts = !kani_attrs.loop_invariant.is_empty()
+            || callees.iter().any(|c| c.def.name().ends_with("kani_register_loop_contract"));
+        Ok(if has_loop_contracts {
+            Kind::LoopContract
+        } else if func.macro_backtrace_len != 0 {
+            Kind::Generated
+        } else {
+            Kind::Standard
+        })
     }
 }
 
@@ -115,11 +319,51 @@ mod conversion {
 
     impl From<SerFunction> for lib::SerFunction {
         fn from(value: SerFunction) -> Self {
-            let SerFunction { hash, def_id, attrs, kind, func, callees_len, callees } = value;
+            let SerFunction {
+                hash,
+                def_id,
+                attrs,
+                kani_attrs,
+                kind,
+                func,
+                func_hash,
+                callees_len,
+                cost,
+                callees,
+                statics,
+                types,
+                opaque,
+                contract,
+                stub_verified,
+                env,
+            } = value;
             let func = func.into();
             let kind = kind.into();
+            let cost = cost.into();
             let callees = vec_convertion(callees);
-            Self { hash, def_id, attrs, kind, func, callees_len, callees }
+            let statics = vec_convertion(statics);
+            let types = vec_convertion(types);
+            let opaque = vec_convertion(opaque);
+            let contract = contract.map(Into::into);
+            let stub_verified = vec_convertion(stub_verified);
+            Self {
+                hash,
+                def_id,
+                attrs,
+                kani_attrs,
+                kind,
+                func,
+                func_hash,
+                callees_len,
+                cost,
+                callees,
+                statics,
+                types,
+                opaque,
+                contract,
+                stub_verified,
+                env,
+            }
         }
     }
 
@@ -128,14 +372,49 @@ mod conversion {
             match value {
                 Kind::Standard => Self::Standard,
                 Kind::Contract => Self::Contract,
+                Kind::LoopContract => Self::LoopContract,
+                Kind::Generated => Self::Generated,
             }
         }
     }
 
+    impl From<Cost> for lib::Cost {
+        fn from(value: Cost) -> Self {
+            let Cost { basic_blocks, back_edges, kani_any, unwind, raw_ptr_ops, score } = value;
+            Self { basic_blocks, back_edges, kani_any, unwind, raw_ptr_ops, score }
+        }
+    }
+
     impl From<Callee> for lib::Callee {
-        fn from(Callee { def_id, func }: Callee) -> Self {
+        fn from(Callee { def_id, func, hash }: Callee) -> Self {
             let func = func.into();
-            Self { def_id, func }
+            Self { def_id, func, hash }
+        }
+    }
+
+    impl From<Static> for lib::Static {
+        fn from(Static { name, ty, hash }: Static) -> Self {
+            Self { name, ty, hash }
+        }
+    }
+
+    impl From<TypeDef> for lib::TypeDef {
+        fn from(TypeDef { name, hash }: TypeDef) -> Self {
+            Self { name, hash }
+        }
+    }
+
+    impl From<Opaque> for lib::Opaque {
+        fn from(Opaque { kind, name, sig, hash }: Opaque) -> Self {
+            Self { kind, name, sig, hash }
+        }
+    }
+
+    impl From<Contract> for lib::Contract {
+        fn from(value: Contract) -> Self {
+            let Contract { target, name, requires, ensures, modifies, closures, hash, proofs } =
+                value;
+            Self { target, name, requires, ensures, modifies, closures, hash, proofs }
         }
     }
 
@@ -166,10 +445,14 @@ mod conversion {
             Self {
                 hash: val.hash.clone(),
                 attrs: val.attrs.clone(),
+                kind: Some(val.kind.into()),
                 name: val.func.name.clone(),
                 file: val.func.file.clone(),
                 callees_len: val.callees_len,
+                cost: Some(val.cost.score),
                 callees: val.callees.iter().map(|c| c.func.name.clone()).collect(),
+                contract: val.contract.as_ref().map(|c| c.target.clone()),
+                stub_verified: val.stub_verified.iter().map(|c| c.target.clone()).collect(),
             }
         }
     }
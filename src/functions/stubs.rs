@@ -0,0 +1,96 @@
+//! Stubs of a harness from `#[kani::stub]` and `#[kani::stub_verified]`.
+//!
+//! kani never verifies the body of a stubbed function, so reachability follows the
+//! replacement instead, and stops at functions replaced by their contracts.
+//!
+//! Paths in stub attributes are written as in the harness source, so they're resolved
+//! from the module of the harness through [`resolve_fn`], and callees are matched by def id.
+
+use super::resolve::resolve_fn;
+use distributed_verification::attrs::KaniAttrs;
+use eyre::{Context, Result};
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::TyCtxt;
+use rustc_smir::rustc_internal;
+use stable_mir::{
+    CrateDef,
+    mir::mono::Instance,
+    ty::{FnDef, GenericArgs, RigidTy, TyKind},
+};
+
+/// What a callee becomes under the stubs of a harness.
+pub enum Stubbed {
+    /// Not stubbed, or the replacement can't be instantiated for the callee.
+    No,
+    /// Replaced by another function.
+    Replaced(Instance),
+    /// Replaced by its contract through `stub_verified`, so its body isn't followed.
+    Verified,
+}
+
+#[derive(Default)]
+pub struct Stubs<'tcx> {
+    tcx: Option<TyCtxt<'tcx>>,
+    /// Original functions and their replacements.
+    replace: Vec<(DefId, FnDef)>,
+    /// Functions in `stub_verified`.
+    verified: Vec<DefId>,
+}
+
+impl<'tcx> Stubs<'tcx> {
+    /// Resolve paths in stub attributes of the harness. Fails if a path doesn't refer
+    /// to exactly one function.
+    pub fn new(tcx: TyCtxt<'tcx>, harness: &Instance, attrs: &KaniAttrs) -> Result<Self> {
+        if !attrs.needs_stubbing() {
+            return Ok(Stubs::default());
+        }
+        let harness = rustc_internal::internal(tcx, harness.def.def_id());
+        let resolve = |path: &str| resolve_fn(tcx, harness, path);
+        let replace = attrs
+            .stubs
+            .iter()
+            .map(|stub| {
+                let original = resolve(&stub.original);
+                let replacement = resolve(&stub.replacement).map(|def| fn_def(tcx, def));
+                let pair = original.and_then(|original| Ok((original, replacement?)));
+                pair.wrap_err_with(|| format!("Invalid stub {stub:?}"))
+            })
+            .collect::<Result<_>>()?;
+        let verified = attrs
+            .stub_verified
+            .iter()
+            .map(|path| resolve(path).wrap_err("Invalid stub_verified"))
+            .collect::<Result<_>>()?;
+        Ok(Stubs { tcx: Some(tcx), replace, verified })
+    }
+
+    pub fn get(&self, inst: &Instance) -> Stubbed {
+        let Some(tcx) = self.tcx else { return Stubbed::No };
+        let def = rustc_internal::internal(tcx, inst.def.def_id());
+        if self.verified.contains(&def) {
+            return Stubbed::Verified;
+        }
+        let replacement = self.replace.iter().find(|(original, _)| *original == def);
+        match replacement.and_then(|&(_, replacement)| resolve(replacement, inst)) {
+            Some(replacement) => Stubbed::Replaced(replacement),
+            None => Stubbed::No,
+        }
+    }
+}
+
+/// The function as a stable FnDef.
+fn fn_def(tcx: TyCtxt, def: DefId) -> FnDef {
+    let ty = rustc_internal::stable(tcx.type_of(def).instantiate_identity());
+    let TyKind::RigidTy(RigidTy::FnDef(def, _)) = ty.kind() else { unreachable!("{ty:?}") };
+    def
+}
+
+/// A generic replacement takes generic arguments of the original, otherwise none.
+fn resolve(def: FnDef, original: &Instance) -> Option<Instance> {
+    Instance::resolve(def, &original.args())
+        .or_else(|_| Instance::resolve(def, &GenericArgs(Vec::new())))
+        .inspect_err(|err| {
+            error!(?err, ?def, ?original, "Can't resolve the replacement, so follow the original")
+        })
+        .ok()
+}
@@ -0,0 +1,43 @@
+//! Collect ADTs (structs, enums and unions) used by a proof and its callees.
+//!
+//! A changed field or `#[repr]` can change verification results without
+//! changing any function body, so ADT definitions are proof dependencies.
+
+use super::cache;
+use indexmap::IndexSet;
+use stable_mir::{
+    mir::mono::Instance,
+    ty::{RigidTy, Ty, TyKind},
+};
+
+/// ADT types in locals (including arguments and return values) of the proof and callees,
+/// and ADTs reachable through their fields. Sorted by type name.
+pub fn collect_adts(proof: &Instance, callees: &IndexSet<Instance>) -> IndexSet<Ty> {
+    let mut adts = IndexSet::new();
+    for inst in std::iter::once(proof).chain(callees) {
+        cache::get_body(inst, |body| {
+            body.locals().iter().for_each(|local| visit_ty(local.ty, &mut adts));
+        });
+    }
+    adts.sort_by_cached_key(|ty| ty.to_string());
+    adts
+}
+
+fn visit_ty(ty: Ty, adts: &mut IndexSet<Ty>) {
+    let TyKind::RigidTy(rigid) = ty.kind() else { return };
+    match rigid {
+        RigidTy::Adt(def, args) => {
+            if adts.insert(ty) {
+                for variant in def.variants() {
+                    for field in variant.fields() {
+                        visit_ty(field.ty_with_args(&args), adts);
+                    }
+                }
+            }
+        }
+        RigidTy::Ref(_, ty, _) | RigidTy::RawPtr(ty, _) | RigidTy::Slice(ty) => visit_ty(ty, adts),
+        RigidTy::Array(ty, _) => visit_ty(ty, adts),
+        RigidTy::Tuple(tys) => tys.into_iter().for_each(|ty| visit_ty(ty, adts)),
+        _ => (),
+    }
+}
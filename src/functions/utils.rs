@@ -1,10 +1,9 @@
-use rustc_middle::ty::TyCtxt;
+use super::hash::ContentHasher;
+use rustc_middle::ty::{GenericArgKind, TyCtxt};
 use rustc_smir::rustc_internal::internal;
 use rustc_span::{Span, source_map::SourceMap};
-use rustc_stable_hash::{StableHasher, hashers::SipHasher128};
 use serde::Serialize;
-use stable_mir::mir::mono::Instance;
-use std::hash::Hasher;
+use stable_mir::{CrateDef, mir::mono::Instance};
 
 /// Source code and potential source code before expansion.
 ///
@@ -45,7 +44,7 @@ pub struct SourceCode {
 }
 
 impl SourceCode {
-    pub fn with_hasher(&self, hasher: &mut StableHasher<SipHasher128>) {
+    pub fn with_hasher(&self, hasher: &mut ContentHasher) {
         hasher.write_str(&self.name);
         hasher.write_str(&self.mangled_name);
         hasher.write_str(&self.kind);
@@ -59,6 +58,14 @@ impl SourceCode {
     }
 }
 
+impl SourceCode {
+    /// A copy for the portable hash profile: the name is replaced with the given
+    /// build-independent one, and the mangled name is left out.
+    pub fn portable(&self, name: String) -> SourceCode {
+        SourceCode { name, mangled_name: String::new(), ..self.clone() }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub struct MacroBacktrace {
     pub callsite: String,
@@ -81,7 +88,7 @@ pub fn source_code_with(
     stable_mir_span: stable_mir::ty::Span,
     tcx: TyCtxt,
     src_map: &SourceMap,
-    path_prefixes: [&str; 2],
+    path_prefixes: &[&str],
 ) -> SourceCode {
     let span = internal(tcx, stable_mir_span);
     let src = span_to_source(span, src_map);
@@ -124,6 +131,22 @@ pub fn source_code_with(
     SourceCode { name, mangled_name, kind, file, src, macro_backtrace_len, macro_backtrace }
 }
 
+/// Instance name from crate name, def path, and type and const arguments,
+/// e.g. `core::ptr::read::<u8>` or `core::array::from_fn::<u8, 3, F>`.
+/// Unlike the mangled name, it doesn't depend on `-C metadata` or the rustc version.
+pub fn portable_name(inst: &Instance, tcx: TyCtxt) -> String {
+    let def_id = internal(tcx, inst.def.def_id());
+    let krate = tcx.crate_name(def_id.krate);
+    let path = format!("{krate}{}", tcx.def_path(def_id).to_string_no_crate_verbose());
+    // lifetimes are erased in instances
+    let args: Vec<_> = internal(tcx, inst.args())
+        .iter()
+        .filter(|arg| !matches!(arg.unpack(), GenericArgKind::Lifetime(_)))
+        .map(|arg| arg.to_string())
+        .collect();
+    if args.is_empty() { path } else { format!("{path}::<{}>", args.join(", ")) }
+}
+
 pub fn vec_convertion<U, T: From<U>>(vec: Vec<U>) -> Vec<T> {
     vec.into_iter().map(T::from).collect()
 }
@@ -0,0 +1,124 @@
+//! Options on how functions and proofs are hashed.
+//!
+//! The options are set on the command line, and passed to the rustc wrapper
+//! through environment variables in cargo mode.
+
+use crate::Result;
+use clap::ValueEnum;
+
+/// An environment variable holding [`HashMode`] for the rustc wrapper.
+pub const ENV_HASH_MODE: &str = "DISTRIBUTED_VERIFICATION_HASH_MODE";
+/// An environment variable holding [`HashProfile`] for the rustc wrapper.
+pub const ENV_HASH_PROFILE: &str = "DISTRIBUTED_VERIFICATION_HASH_PROFILE";
+/// An environment variable holding [`HashAlgorithm`] for the rustc wrapper.
+pub const ENV_HASH_ALGORITHM: &str = "DISTRIBUTED_VERIFICATION_HASH_ALGORITHM";
+/// An environment variable telling the rustc wrapper to mix the environment fingerprint
+/// into proof hashes if it's `1`.
+pub const ENV_HASH_ENV: &str = "DISTRIBUTED_VERIFICATION_HASH_ENV";
+/// An environment variable holding the directory of `--explain-hash` for the rustc wrapper.
+pub const ENV_EXPLAIN_HASH: &str = "DISTRIBUTED_VERIFICATION_EXPLAIN_HASH";
+/// An environment variable holding comma-separated `--explain-proof` for the rustc wrapper.
+pub const ENV_EXPLAIN_PROOF: &str = "DISTRIBUTED_VERIFICATION_EXPLAIN_PROOF";
+
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct HashArgs {
+    /// What a function hash is computed from.
+    #[arg(long, value_enum, default_value_t)]
+    pub hash_mode: HashMode,
+    /// Which build-dependent fields go into a function hash.
+    #[arg(long, value_enum, default_value_t)]
+    pub hash_profile: HashProfile,
+    /// Hash function. Hash values are hex strings prefixed with the algorithm name.
+    #[arg(long, value_enum, default_value_t)]
+    pub hash_algorithm: HashAlgorithm,
+    /// Mix the environment fingerprint (rustc version, target, session options such as
+    /// overflow checks and cfg values, and kani version) into proof hashes, so proofs
+    /// rerun after a toolchain bump.
+    #[arg(long, default_value_t = false)]
+    pub hash_env: bool,
+    /// Write the exact input of the hasher for each proof to `{dir}/{proof}.txt`,
+    /// including inputs of function, static and type hashes it's built from.
+    /// Dumps from two runs can be compared with ordinary diff tools.
+    #[arg(long, value_name = "DIR")]
+    pub explain_hash: Option<String>,
+    /// Only explain proofs whose names contain one of the given strings.
+    #[arg(long, requires = "explain_hash")]
+    pub explain_proof: Vec<String>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HashMode {
+    /// Source text behind the body span, including comments and formatting.
+    #[default]
+    Source,
+    /// Source tokens without comments, doc comments and whitespace.
+    /// The full source is still emitted in JSON.
+    Tokens,
+    /// Canonical rendering of MIR without spans, local names and debug info,
+    /// so only semantic changes alter the hash.
+    Mir,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HashProfile {
+    /// Include mangled names, which depend on `-C metadata`, the rustc version and cargo flags.
+    #[default]
+    Exact,
+    /// Leave out mangled names, and name instances by crate name, def path and type arguments,
+    /// so the same source gets the same hash in single file, cargo and build-std builds.
+    Portable,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    /// 128-bit SipHash from rustc, e.g. `sip128:` followed by 32 hex digits.
+    #[default]
+    Sip128,
+    /// SHA-256, e.g. `sha256:` followed by 64 hex digits. Slower, but collision resistant
+    /// for long-lived result caches.
+    Sha256,
+}
+
+impl HashArgs {
+    /// Environment variables to pass the options to the rustc wrapper.
+    pub fn envs(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (ENV_HASH_MODE, value_name(self.hash_mode)),
+            (ENV_HASH_PROFILE, value_name(self.hash_profile)),
+            (ENV_HASH_ALGORITHM, value_name(self.hash_algorithm)),
+            (ENV_HASH_ENV, if self.hash_env { "1" } else { "0" }.to_owned()),
+            (ENV_EXPLAIN_HASH, self.explain_hash.clone().unwrap_or_default()),
+            (ENV_EXPLAIN_PROOF, self.explain_proof.join(",")),
+        ]
+    }
+
+    /// Read options from environment variables set by [`HashArgs::envs`].
+    /// Unset variables fall back to default values.
+    pub fn from_env() -> Result<Self> {
+        let mut args = HashArgs::default();
+        if let Ok(mode) = std::env::var(ENV_HASH_MODE) {
+            args.hash_mode = parse_value(ENV_HASH_MODE, &mode)?;
+        }
+        if let Ok(profile) = std::env::var(ENV_HASH_PROFILE) {
+            args.hash_profile = parse_value(ENV_HASH_PROFILE, &profile)?;
+        }
+        if let Ok(algorithm) = std::env::var(ENV_HASH_ALGORITHM) {
+            args.hash_algorithm = parse_value(ENV_HASH_ALGORITHM, &algorithm)?;
+        }
+        args.hash_env = std::env::var(ENV_HASH_ENV).as_deref() == Ok("1");
+        args.explain_hash = std::env::var(ENV_EXPLAIN_HASH).ok().filter(|dir| !dir.is_empty());
+        if let Ok(proofs) = std::env::var(ENV_EXPLAIN_PROOF) {
+            args.explain_proof =
+                proofs.split(',').filter(|p| !p.is_empty()).map(Into::into).collect();
+        }
+        Ok(args)
+    }
+}
+
+fn value_name(value: impl ValueEnum) -> String {
+    value.to_possible_value().unwrap().get_name().to_owned()
+}
+
+fn parse_value<T: ValueEnum>(env: &str, value: &str) -> Result<T> {
+    T::from_str(value, false).map_err(|err| eyre!("Invalid {env}={value:?}: {err}"))
+}
@@ -1,4 +1,4 @@
-use crate::{Kind, Result, SerFunction};
+use crate::{Result, SerFunction};
 use eyre::ContextCompat;
 use indexmap::{IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
@@ -122,9 +122,10 @@ pub fn check_proofs(list: &KaniList, v_ser_fun: &[SerFunction]) -> Result<()> {
 
     // double check
     for (&(path, proof), &(_, kind)) in &map {
-        let harnesses = match kind {
-            Kind::Standard => &list.standard_harnesses[path],
-            Kind::Contract => &list.contract_harnesses[path],
+        let harnesses = if kind.is_contract() {
+            &list.contract_harnesses[path]
+        } else {
+            &list.standard_harnesses[path]
         };
         harnesses.get(proof).with_context(|| {
             format!(
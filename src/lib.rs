@@ -1,4 +1,4 @@
-use eyre::Result;
+use eyre::{Context, ContextCompat, Result};
 use serde::{Deserialize, Serialize};
 
 #[macro_use]
@@ -6,41 +6,195 @@ extern crate tracing;
 #[macro_use]
 extern crate eyre;
 
+pub mod attrs;
+pub mod cargo;
+pub mod deps;
+pub mod diff;
+pub mod distribute;
+pub mod filter;
+pub mod hash;
 pub mod kani_list;
+pub mod logger;
+pub mod merge;
+pub mod result_cache;
+pub mod run;
+pub mod shard;
+pub mod summary;
 
 /// A kani proof with its file source, attributes, and raw function content.
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct SerFunction {
     pub hash: String,
-    /// DefId in stable_mir.
+    /// Def path with crate name and stable crate id, e.g. `core[8d2b]::ptr::read`.
+    /// Unlike a DefId in stable_mir, it's the same across sessions and machines.
     pub def_id: String,
     /// Attributes are attached the function, but it seems that attributes
     /// and function must be separated to query.
     pub attrs: Vec<String>,
+    /// kanitool attributes parsed from `attrs`. Raw `attrs` are part of the proof hash.
+    #[serde(default)]
+    pub kani_attrs: attrs::KaniAttrs,
     /// Proof kind
     pub kind: Kind,
     /// Raw function string, including name, signature, and body.
     pub func: SourceCode,
+    /// Hash of the proof function alone. The proof hash is built from it,
+    /// attributes, and hashes of callees.
+    #[serde(default)]
+    pub func_hash: String,
     /// Count of callees.
     pub callees_len: usize,
+    /// Estimated verification cost from MIR.
+    #[serde(default)]
+    pub cost: Cost,
     /// Recursive function calls inside the body.
     pub callees: Vec<Callee>,
+    /// Statics reachable from the proof. Their values are part of the proof hash.
+    #[serde(default)]
+    pub statics: Vec<Static>,
+    /// ADTs used by the proof and callees. Their definitions are part of the proof hash.
+    #[serde(default)]
+    pub types: Vec<TypeDef>,
+    /// Callees without a body, like intrinsics and foreign functions.
+    /// Their identities are part of the proof hash.
+    #[serde(default)]
+    pub opaque: Vec<Opaque>,
+    /// The contract checked by `proof_for_contract`, resolved from the attribute path.
+    /// None for standard proofs or if the target isn't found among callees.
+    #[serde(default)]
+    pub contract: Option<Contract>,
+    /// Contracts of callees replaced through `stub_verified`. Contract closures of the
+    /// targets are callees of the proof, but other callees of the targets are not.
+    #[serde(default)]
+    pub stub_verified: Vec<Contract>,
+    /// Fingerprint of the rustc version, target, session options and kani version.
+    /// It's mixed into the proof hash with `--hash-env`.
+    #[serde(default)]
+    pub env: String,
 }
 
 /// kani proof kind
-#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum Kind {
     /// `#[kani::proof]` (actually `kanitool::proof`)
     #[default]
     Standard,
     /// `#[kani::proof_for_contract]` (actually `kanitool::proof_for_contract`)
     Contract,
+    /// `#[kani::proof]` reaching loops with `#[kani::loop_invariant]`, which needs
+    /// `-Zloop-contracts`.
+    LoopContract,
+    /// `#[kani::proof]` generated by a macro, e.g. one harness per type from `macro_rules!`.
+    Generated,
+}
+
+impl Kind {
+    /// Proof kind from kanitool attributes. None if no proof kind attribute is found.
+    ///
+    /// Only attributes on the proof are seen, so [`Kind::LoopContract`] is found only if
+    /// the proof has loop invariants itself, and [`Kind::Generated`] is never found.
+    /// It's a fallback for JSON without `kind`, which is detected from callees and spans.
+    pub fn from_attrs(attrs: &[String]) -> Option<Self> {
+        let attrs = attrs::KaniAttrs::parse(attrs);
+        if attrs.proof_for_contract.is_some() {
+            Some(Kind::Contract)
+        } else if !attrs.proof {
+            None
+        } else if !attrs.loop_invariant.is_empty() {
+            Some(Kind::LoopContract)
+        } else {
+            Some(Kind::Standard)
+        }
+    }
+
+    /// Is it listed in `contract_harnesses` rather than `standard_harnesses` by `kani list`?
+    pub fn is_contract(self) -> bool {
+        self == Kind::Contract
+    }
+}
+
+/// Estimated verification cost. Counts are summed over the proof and all its callees.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Cost {
+    /// Count of basic blocks.
+    pub basic_blocks: usize,
+    /// Count of loop back-edges, i.e. jumps to a basic block not after the current one.
+    pub back_edges: usize,
+    /// Count of `kani::any` and `kani::any_where` calls.
+    pub kani_any: usize,
+    /// Unwind bound from `#[kani::unwind]` on the proof.
+    pub unwind: Option<u32>,
+    /// Count of raw pointer operations: dereferences, address-of, and pointer casts.
+    pub raw_ptr_ops: usize,
+    /// Weighted sum of the counts above, for schedulers to compare proofs.
+    /// Zero means the cost is unknown, e.g. in JSON emitted by older versions.
+    pub score: u64,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Callee {
+    /// Def path with crate name and stable crate id, like [`SerFunction::def_id`].
     pub def_id: String,
     pub func: SourceCode,
+    /// Hash of the callee function alone.
+    #[serde(default)]
+    pub hash: String,
+}
+
+/// A static that a proof depends on.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Static {
+    pub name: String,
+    /// Type of the static.
+    pub ty: String,
+    /// Hash of the name, type, and initializer value.
+    pub hash: String,
+}
+
+/// An ADT (struct, enum, or union) that a proof uses.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct TypeDef {
+    /// Type name with generic arguments.
+    pub name: String,
+    /// Hash of the variants, field names and types, and layout.
+    pub hash: String,
+}
+
+/// A callee without a body, identified by instance kind, def path, and signature.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Opaque {
+    /// Instance kind, e.g. `Intrinsic`, `Virtual`, or `Item` for foreign functions.
+    pub kind: String,
+    /// Def path with generic arguments.
+    pub name: String,
+    /// Function signature.
+    pub sig: String,
+    /// Hash of the kind, name, and signature.
+    pub hash: String,
+}
+
+/// The contract of a function, checked by `proof_for_contract` or assumed by `stub_verified`.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Contract {
+    /// Def path of the function with the contract, like [`SerFunction::def_id`].
+    pub target: String,
+    /// Function name with generic arguments.
+    pub name: String,
+    /// Clauses of `requires` on the target.
+    pub requires: Vec<String>,
+    /// Clauses of `ensures` on the target.
+    pub ensures: Vec<String>,
+    /// Clauses of `modifies` on the target.
+    pub modifies: Vec<String>,
+    /// Names of closures generated for the contract in the body of the target.
+    /// kani checks or calls them in place of the target.
+    pub closures: Vec<String>,
+    /// Hash of the target, its kanitool attributes, and the closures.
+    /// It's part of the proof hash.
+    pub hash: String,
+    /// Names of proofs in the same crate checking the contract.
+    /// Only filled for `stub_verified`; empty if the contract is never checked.
+    pub proofs: Vec<String>,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
@@ -104,14 +258,101 @@ pub fn kani_path() -> String {
     path
 }
 
+/// Output of `kani --version` for the installation at [`kani_path`], which is the same
+/// version string as in run reports and the result cache.
+///
+/// kani in `bin` of the path is preferred, since `KANI_DIR` may point to a development build
+/// like `kani/target/kani`, then kani in PATH. The folder name is only a fallback when
+/// neither runs, which is `kani-0.62.0` for an installation from `cargo kani setup`.
+pub fn kani_version_of(kani_path: &str) -> String {
+    let path = std::path::Path::new(kani_path);
+    let version = |kani: &std::ffi::OsStr| {
+        let out = std::process::Command::new(kani).arg("--version").output().ok()?;
+        let version = std::str::from_utf8(&out.stdout).ok()?.trim();
+        (out.status.success() && !version.is_empty()).then(|| version.to_owned())
+    };
+    let bundled = path.join("bin").join("kani");
+    version(bundled.as_os_str()).or_else(|| version("kani".as_ref())).unwrap_or_else(|| {
+        let name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+        warn!(kani_path, "Failed to run `kani --version`, so use the folder name {name:?}");
+        name.into_owned()
+    })
+}
+
+/// Rustc args to compile code in the way kani does, given the [`kani_path`].
+///
+/// The crate type and source file are not included.
+pub fn kani_rustc_args(kani_path: &str) -> Vec<String> {
+    [
+        "--cfg=kani",
+        "-Zcrate-attr=feature(register_tool)",
+        "-Zcrate-attr=register_tool(kanitool)",
+        "--sysroot",
+        kani_path,
+        "-L",
+        &format!("{kani_path}/lib"),
+        "--extern",
+        "kani",
+        "--extern",
+        &format!("noprelude:std={kani_path}/lib/libstd.rlib"),
+        "-Zunstable-options",
+        "-Zalways-encode-mir",
+        "-Zmir-enable-passes=-RemoveStorageMarkers",
+    ]
+    .map(String::from)
+    .into()
+}
+
+/// Rustc args to compile the standard library in the way kani does, given the [`kani_path`].
+///
+/// `kani_core` is injected, so `kani` module in core can be recognized.
+pub fn kani_std_rustc_args(kani_path: &str) -> Result<Vec<String>> {
+    // -Lpath must be an absolute path
+    let kani_lib = std::path::Path::new(kani_path).join("no_core").join("lib");
+    let kani_lib = kani_lib.canonicalize().with_context(|| format!("{kani_lib:?} not found"))?;
+    let kani_lib = kani_lib.to_str().context("Non UTF-8 path")?;
+    Ok([
+        "-Coverflow-checks=on",
+        "-Zunstable-options",
+        "-Ztrim-diagnostic-paths=no",
+        "-Zhuman_readable_cgu_names",
+        "-Zalways-encode-mir",
+        "--cfg=kani",
+        "-Zcrate-attr=feature(register_tool)",
+        "-Zcrate-attr=register_tool(kanitool)",
+        "-Cpanic=abort",
+        "-Csymbol-mangling-version=v0",
+        "-Zpanic_abort_tests=yes",
+        "-Zmir-enable-passes=-RemoveStorageMarkers",
+        "--check-cfg=cfg(kani)",
+        "-L",
+        kani_lib,
+        "--extern=kani_core",
+    ]
+    .map(String::from)
+    .into())
+}
+
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct SimplifiedSerFunction {
     pub hash: String,
     pub attrs: Vec<String>,
+    /// [`SerFunction::kind`]. None in JSON files emitted before it was added.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<Kind>,
     pub name: String,
     pub file: String,
     pub callees_len: usize,
+    /// [`Cost::score`].
+    #[serde(default)]
+    pub cost: Option<u64>,
     pub callees: Vec<String>,
+    /// [`Contract::target`] of [`SerFunction::contract`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contract: Option<String>,
+    /// [`Contract::target`] of each of [`SerFunction::stub_verified`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stub_verified: Vec<String>,
 }
 
 impl From<&SerFunction> for SimplifiedSerFunction {
@@ -119,10 +360,145 @@ impl From<&SerFunction> for SimplifiedSerFunction {
         SimplifiedSerFunction {
             hash: val.hash.clone(),
             attrs: val.attrs.clone(),
+            kind: Some(val.kind),
             name: val.func.name.clone(),
             file: val.func.file.clone(),
             callees_len: val.callees_len,
+            cost: Some(val.cost.score),
             callees: val.callees.iter().map(|c| c.func.name.clone()).collect(),
+            contract: val.contract.as_ref().map(|c| c.target.clone()),
+            stub_verified: val.stub_verified.iter().map(|c| c.target.clone()).collect(),
+        }
+    }
+}
+
+/// Proofs in a JSON file emitted by `--json`, either full or simplified.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum Proofs {
+    Full(Vec<SerFunction>),
+    Simplified(Vec<SimplifiedSerFunction>),
+}
+
+/// Borrowed fields shared by full and simplified proofs.
+#[derive(Debug, Clone)]
+pub struct ProofView<'a> {
+    pub hash: &'a str,
+    /// [`SerFunction::kind`], or [`Kind::from_attrs`] for simplified JSON without `kind`.
+    pub kind: Option<Kind>,
+    pub attrs: &'a [String],
+    pub name: &'a str,
+    pub file: &'a str,
+    /// Callee names.
+    pub callees: Vec<&'a str>,
+    /// [`Cost::score`]. None if unknown.
+    pub cost: Option<u64>,
+    /// Def path of the `proof_for_contract` target. None if unknown.
+    pub contract: Option<&'a str>,
+    /// Def paths of `stub_verified` targets reachable from the proof.
+    pub stub_verified: Vec<&'a str>,
+}
+
+impl SerFunction {
+    pub fn view(&self) -> ProofView<'_> {
+        ProofView {
+            hash: &self.hash,
+            kind: Some(self.kind),
+            attrs: &self.attrs,
+            name: &self.func.name,
+            file: &self.func.file,
+            callees: self.callees.iter().map(|c| &*c.func.name).collect(),
+            cost: (self.cost.score != 0).then_some(self.cost.score),
+            contract: self.contract.as_ref().map(|c| &*c.target),
+            stub_verified: self.stub_verified.iter().map(|c| &*c.target).collect(),
+        }
+    }
+}
+
+impl SimplifiedSerFunction {
+    pub fn view(&self) -> ProofView<'_> {
+        ProofView {
+            hash: &self.hash,
+            kind: self.kind.or_else(|| Kind::from_attrs(&self.attrs)),
+            attrs: &self.attrs,
+            name: &self.name,
+            file: &self.file,
+            callees: self.callees.iter().map(|c| &**c).collect(),
+            cost: self.cost,
+            contract: self.contract.as_deref(),
+            stub_verified: self.stub_verified.iter().map(|s| &**s).collect(),
+        }
+    }
+}
+
+impl Proofs {
+    pub fn len(&self) -> usize {
+        match self {
+            Proofs::Full(v) => v.len(),
+            Proofs::Simplified(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn views(&self) -> Vec<ProofView<'_>> {
+        match self {
+            Proofs::Full(v) => v.iter().map(SerFunction::view).collect(),
+            Proofs::Simplified(v) => v.iter().map(SimplifiedSerFunction::view).collect(),
+        }
+    }
+
+    /// Only keep proofs that satisfy the predicate.
+    pub fn retain(&mut self, mut f: impl FnMut(&ProofView) -> bool) {
+        match self {
+            Proofs::Full(v) => v.retain(|p| f(&p.view())),
+            Proofs::Simplified(v) => v.retain(|p| f(&p.view())),
+        }
+    }
+
+    /// Clone proofs at given positions.
+    pub fn select(&self, positions: &[usize]) -> Proofs {
+        match self {
+            Proofs::Full(v) => Proofs::Full(positions.iter().map(|&i| v[i].clone()).collect()),
+            Proofs::Simplified(v) => {
+                Proofs::Simplified(positions.iter().map(|&i| v[i].clone()).collect())
+            }
+        }
+    }
+
+    pub fn to_simplified(&self) -> Vec<SimplifiedSerFunction> {
+        match self {
+            Proofs::Full(v) => v.iter().map(SimplifiedSerFunction::from).collect(),
+            Proofs::Simplified(v) => v.clone(),
         }
     }
+
+    pub fn into_simplified(self) -> Vec<SimplifiedSerFunction> {
+        match self {
+            Proofs::Full(v) => v.iter().map(SimplifiedSerFunction::from).collect(),
+            Proofs::Simplified(v) => v,
+        }
+    }
+}
+
+/// Read a proof JSON file emitted by `--json`.
+pub fn read_proofs(path: &str) -> Result<Proofs> {
+    let _span = debug_span!("read_proofs", path).entered();
+    let file = std::fs::File::open(path)?;
+    serde_json::from_reader(std::io::BufReader::new(file))
+        .with_context(|| format!("{path} is neither full nor simplified proof JSON"))
+}
+
+/// Write pretty JSON to the path, or print to stdout if the path is None.
+pub fn write_json<T: Serialize + ?Sized>(path: Option<&str>, value: &T) -> Result<()> {
+    let writer: Box<dyn std::io::Write> = match path {
+        Some(path) => {
+            let _span = error_span!("write_json", path).entered();
+            Box::new(std::fs::File::create(path)?)
+        }
+        None => Box::new(std::io::stdout()),
+    };
+    serde_json::to_writer_pretty(writer, value).context("Failed to write json")
 }
@@ -4,6 +4,7 @@ extern crate rustc_data_structures;
 extern crate rustc_driver;
 extern crate rustc_hir;
 extern crate rustc_interface;
+extern crate rustc_lexer;
 extern crate rustc_middle;
 extern crate rustc_session;
 #[macro_use]
@@ -12,13 +13,12 @@ extern crate rustc_span;
 extern crate rustc_stable_hash;
 extern crate stable_mir;
 
-use distributed_verification::{SimplifiedSerFunction, kani_list::check_proofs};
-use eyre::{Context, Result};
-use functions::{clear_rustc_ctx, set_rustc_ctx};
+use distributed_verification::{SimplifiedSerFunction, kani_list::check_proofs, logger};
+use eyre::{Context, ContextCompat, Result};
+use functions::{clear_rustc_ctx, env_fingerprint, set_hash_args, set_rustc_ctx};
 
 mod cli;
 mod functions;
-mod logger;
 
 #[macro_use]
 extern crate tracing;
@@ -27,7 +27,19 @@ extern crate eyre;
 
 fn main() -> Result<()> {
     logger::init();
-    let run = cli::parse()?;
+    let run = match cli::parse()? {
+        cli::Mode::Analyze(run) => *run,
+        cli::Mode::Diff(diff) => return diff.run(),
+        cli::Mode::Rustc(args) => {
+            let (rustc, args) =
+                args.split_first().context("No rustc path for the rustc wrapper")?;
+            let status = std::process::Command::new(rustc)
+                .args(args)
+                .status()
+                .with_context(|| format!("Failed to run {rustc}"))?;
+            std::process::exit(status.code().unwrap_or(1));
+        }
+    };
 
     let res = run_with_tcx!(run.rustc_args, |tcx| {
         // let crates = stable_mir::external_crates();
@@ -48,11 +60,18 @@ fn main() -> Result<()> {
         // }
 
         set_rustc_ctx(tcx);
+        set_hash_args(run.hash.clone());
 
-        let output = functions::analyze(tcx);
+        let kani_list_version = run.kani_list.as_ref().map(|list| &*list.kani_version);
+        let env = env_fingerprint(tcx, run.kani_version.as_deref(), kani_list_version);
+        let output = functions::analyze(tcx, &env, run.hash.hash_env);
 
         clear_rustc_ctx();
 
+        let output = match output {
+            Ok(output) => output,
+            Err(err) => return ControlFlow::<Result<()>, Result<()>>::Break(Err(err)),
+        };
         let output = functions::vec_convertion(output);
         let mut res_check_kani_list = Ok(());
         if let Some(kani_list) = run.kani_list {
@@ -60,6 +79,10 @@ fn main() -> Result<()> {
         }
 
         let res_json = (|| {
+            if let Some(dir) = &run.hash.explain_hash {
+                functions::write_explanations(dir, &run.hash.explain_proof, &output)?;
+            }
+
             let writer: Box<dyn std::io::Write>;
             match &run.json {
                 Some(path) => {
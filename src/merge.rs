@@ -0,0 +1,62 @@
+//! Merge proof JSON outputs, e.g. ones emitted for different crates.
+
+use crate::{
+    ProofView, Proofs, Result, SerFunction, SimplifiedSerFunction, read_proofs, write_json,
+};
+use indexmap::IndexMap;
+
+/// Merge proof JSON files into one.
+///
+/// The output is full JSON if all inputs are full, otherwise simplified.
+#[derive(clap::Args, Debug)]
+pub struct MergeArgs {
+    /// JSON files of proofs.
+    #[arg(required = true)]
+    pub files: Vec<String>,
+
+    /// Path to write the merged JSON. Print to stdout if not set.
+    #[arg(long)]
+    pub json: Option<String>,
+}
+
+impl MergeArgs {
+    pub fn run(&self) -> Result<()> {
+        let v_proofs = self.files.iter().map(|path| read_proofs(path)).collect::<Result<_>>()?;
+        let merged = merge(v_proofs)?;
+        info!(files = self.files.len(), proofs = merged.len());
+        write_json(self.json.as_deref(), &merged)
+    }
+}
+
+/// Concatenate proofs in order. The same proof (w.r.t. file and name) is only
+/// kept once, and it's an error if the same proof has different hash values.
+pub fn merge(v_proofs: Vec<Proofs>) -> Result<Proofs> {
+    if v_proofs.iter().all(|p| matches!(p, Proofs::Full(_))) {
+        let v = v_proofs.into_iter().flat_map(|p| match p {
+            Proofs::Full(v) => v,
+            Proofs::Simplified(_) => unreachable!(),
+        });
+        dedup(v, SerFunction::view).map(Proofs::Full)
+    } else {
+        let v = v_proofs.into_iter().flat_map(Proofs::into_simplified);
+        dedup(v, SimplifiedSerFunction::view).map(Proofs::Simplified)
+    }
+}
+
+fn dedup<T>(v: impl Iterator<Item = T>, view: impl Fn(&T) -> ProofView) -> Result<Vec<T>> {
+    let mut map = IndexMap::<(String, String), T>::new();
+    for proof in v {
+        let ProofView { file, name, hash, .. } = view(&proof);
+        let key = (file.to_owned(), name.to_owned());
+        if let Some(existing) = map.get(&key) {
+            let existing_hash = view(existing).hash;
+            ensure!(
+                existing_hash == hash,
+                "The proof {key:?} has different hash values: {existing_hash} vs {hash}"
+            );
+            continue;
+        }
+        map.insert(key, proof);
+    }
+    Ok(map.into_values().collect())
+}
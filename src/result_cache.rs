@@ -0,0 +1,356 @@
+//! Content-addressed cache of verification results.
+//!
+//! An entry is keyed by the proof hash, the kani version and extra kani args, so a proof
+//! is only verified again when its code, kani or the args change. Only successful results
+//! are cached, since failures may be transient, like timeouts or running out of memory.
+//!
+//! Layout: `{dir}/{key[..2]}/{key}.json`, where key is the SHA-256 digest
+//! of the kani version, the kani args and the proof hash.
+
+use crate::{
+    Result, read_proofs,
+    run::{ProofResult, RunReport, read_report},
+    write_json,
+};
+use eyre::Context;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Look up, insert and garbage-collect cached verification results.
+#[derive(clap::Args, Debug)]
+pub struct CacheArgs {
+    /// Directory of the cache.
+    #[arg(long, default_value = "target/distributed-verification/cache")]
+    pub dir: String,
+
+    #[command(subcommand)]
+    pub command: CacheCommand,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum CacheCommand {
+    /// Split proofs in a JSON into cached and missing ones.
+    Lookup {
+        /// JSON file of proofs.
+        file: String,
+        /// Output of `kani --version` the results must come from.
+        #[arg(long)]
+        kani_version: String,
+        /// Extra kani args the results must come from, as passed to `run --kani-args`.
+        #[arg(long, allow_hyphen_values = true)]
+        kani_args: Vec<String>,
+        /// Path to write the lookup JSON. Print to stdout if not set.
+        #[arg(long)]
+        json: Option<String>,
+    },
+    /// Insert results from a report JSON emitted by `run`.
+    Insert {
+        /// Run report JSON.
+        report: String,
+    },
+    /// Remove entries meeting any condition. At least one condition or `--all` is required.
+    Gc {
+        /// Remove entries from other kani versions.
+        #[arg(long)]
+        kani_version: Option<String>,
+        /// Remove entries older than the days.
+        #[arg(long)]
+        max_age_days: Option<u64>,
+        /// Remove entries whose proof hash is not in the JSON file of proofs.
+        #[arg(long)]
+        keep: Option<String>,
+        /// Remove all entries.
+        #[arg(long, conflicts_with_all = ["kani_version", "max_age_days", "keep"])]
+        all: bool,
+    },
+}
+
+impl CacheArgs {
+    pub fn run(&self) -> Result<()> {
+        let cache = ResultCache::new(&self.dir);
+        match &self.command {
+            CacheCommand::Lookup { file, kani_version, kani_args, json } => {
+                let proofs = read_proofs(file)?;
+                let mut lookup = Lookup::default();
+                for proof in proofs.views() {
+                    match cache.get(proof.hash, kani_version, kani_args)? {
+                        Some(entry) => lookup.cached.push(entry),
+                        None => lookup.missing.push(MissingProof {
+                            file: proof.file.to_owned(),
+                            name: proof.name.to_owned(),
+                            hash: proof.hash.to_owned(),
+                        }),
+                    }
+                }
+                info!(cached = lookup.cached.len(), missing = lookup.missing.len());
+                write_json(json.as_deref(), &lookup)
+            }
+            CacheCommand::Insert { report } => {
+                let report = read_report(report)?;
+                let inserted = cache.insert_report(&report)?;
+                info!(inserted, total = report.results.len());
+                Ok(())
+            }
+            CacheCommand::Gc { kani_version, max_age_days, keep, all } => {
+                let keep = match keep {
+                    Some(file) => {
+                        let proofs = read_proofs(file)?;
+                        Some(proofs.views().iter().map(|p| p.hash.to_owned()).collect())
+                    }
+                    None => None,
+                };
+                let gc = Gc {
+                    kani_version: kani_version.clone(),
+                    max_age_secs: max_age_days.map(|d| d * 24 * 60 * 60),
+                    keep,
+                    all: *all,
+                };
+                let removed = cache.gc(&gc)?;
+                info!(removed);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Result of looking up proofs in the cache.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Lookup {
+    pub cached: Vec<CacheEntry>,
+    pub missing: Vec<MissingProof>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct MissingProof {
+    pub file: String,
+    pub name: String,
+    pub hash: String,
+}
+
+/// A cached verification result.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CacheEntry {
+    /// Proof hash.
+    pub hash: String,
+    /// Output of `kani --version`.
+    pub kani_version: String,
+    /// Extra kani args for all proofs in the run.
+    #[serde(default)]
+    pub kani_args: Vec<String>,
+    /// Proof file and name when the entry is inserted. They are informational,
+    /// since the same proof may be moved or renamed.
+    pub file: String,
+    pub name: String,
+    /// Verdict: does kani exit successfully? Always true for entries inserted now.
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    /// Wall time in seconds.
+    pub duration_secs: f64,
+    /// Path to kani output when the entry is inserted.
+    pub log: String,
+    /// SHA-256 digest of kani output.
+    pub log_digest: String,
+    /// Unix time in seconds when the entry is inserted.
+    pub created_secs: u64,
+}
+
+impl CacheEntry {
+    pub fn new(result: &ProofResult, kani_version: &str, kani_args: &[String]) -> Self {
+        CacheEntry {
+            hash: result.hash.clone(),
+            kani_version: kani_version.to_owned(),
+            kani_args: kani_args.to_vec(),
+            file: result.file.clone(),
+            name: result.name.clone(),
+            success: result.success,
+            exit_code: result.exit_code,
+            duration_secs: result.duration_secs,
+            log: result.log.clone(),
+            log_digest: result.log_digest.clone(),
+            created_secs: now_secs(),
+        }
+    }
+}
+
+impl From<CacheEntry> for ProofResult {
+    fn from(entry: CacheEntry) -> Self {
+        ProofResult {
+            file: entry.file,
+            name: entry.name,
+            hash: entry.hash,
+            success: entry.success,
+            exit_code: entry.exit_code,
+            duration_secs: entry.duration_secs,
+            log: entry.log,
+            log_digest: entry.log_digest,
+            cached: true,
+        }
+    }
+}
+
+/// Conditions to remove entries. An entry is removed if it meets any one.
+/// Without any condition, nothing is removed unless `all` is set.
+#[derive(Debug, Default)]
+pub struct Gc {
+    /// Remove entries whose kani version differs.
+    pub kani_version: Option<String>,
+    /// Remove entries older than the seconds.
+    pub max_age_secs: Option<u64>,
+    /// Remove entries whose proof hash is not in the set.
+    pub keep: Option<HashSet<String>>,
+    /// Remove all entries.
+    pub all: bool,
+}
+
+impl Gc {
+    fn has_condition(&self) -> bool {
+        self.kani_version.is_some() || self.max_age_secs.is_some() || self.keep.is_some()
+    }
+
+    fn removes(&self, entry: &CacheEntry, now: u64) -> bool {
+        let version = self.kani_version.as_ref().is_some_and(|v| *v != entry.kani_version);
+        let age = self.max_age_secs.is_some_and(|max| now.saturating_sub(entry.created_secs) > max);
+        let keep = self.keep.as_ref().is_some_and(|set| !set.contains(&entry.hash));
+        version || age || keep || self.all
+    }
+}
+
+/// On-disk cache of verification results.
+#[derive(Debug, Clone)]
+pub struct ResultCache {
+    dir: PathBuf,
+}
+
+impl ResultCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        ResultCache { dir: dir.into() }
+    }
+
+    /// Cache key of a proof hash under a kani version and extra kani args.
+    pub fn key(hash: &str, kani_version: &str, kani_args: &[String]) -> String {
+        // args are separated by NUL, which can't appear in them
+        let args = kani_args.join("\0");
+        sha256_hex(format!("{kani_version}\n{args}\n{hash}").as_bytes())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(&key[..2]).join(format!("{key}.json"))
+    }
+
+    /// A successful result of the proof hash under the kani version and args.
+    pub fn get(
+        &self,
+        hash: &str,
+        kani_version: &str,
+        kani_args: &[String],
+    ) -> Result<Option<CacheEntry>> {
+        let path = self.path(&Self::key(hash, kani_version, kani_args));
+        if !path.exists() {
+            return Ok(None);
+        }
+        let entry = read_entry(&path)?;
+        // guard against collisions and entries written by hand or by older versions,
+        // which also cached failures
+        let matches = entry.hash == hash
+            && entry.kani_version == kani_version
+            && entry.kani_args == kani_args;
+        Ok((matches && entry.success).then_some(entry))
+    }
+
+    /// Insert or replace an entry. The file is written to a temporary path first,
+    /// so concurrent readers never see a partial entry.
+    pub fn insert(&self, entry: &CacheEntry) -> Result<()> {
+        let path = self.path(&Self::key(&entry.hash, &entry.kani_version, &entry.kani_args));
+        let _span = debug_span!("insert", ?path).entered();
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+        write_json(Some(tmp.to_str().unwrap()), entry)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// Insert a fresh result if it's successful, and return whether it's inserted.
+    /// Failures and cache hits are skipped.
+    pub fn insert_result(
+        &self,
+        result: &ProofResult,
+        kani_version: &str,
+        kani_args: &[String],
+    ) -> Result<bool> {
+        if result.cached || !result.success {
+            return Ok(false);
+        }
+        self.insert(&CacheEntry::new(result, kani_version, kani_args))?;
+        Ok(true)
+    }
+
+    /// Insert successful results of a run report, and return the count of inserted entries.
+    pub fn insert_report(&self, report: &RunReport) -> Result<usize> {
+        let mut inserted = 0;
+        for result in &report.results {
+            if self.insert_result(result, &report.kani_version, &report.kani_args)? {
+                inserted += 1;
+            }
+        }
+        Ok(inserted)
+    }
+
+    /// All entries in the cache with their paths.
+    pub fn entries(&self) -> Result<Vec<(PathBuf, CacheEntry)>> {
+        let mut entries = Vec::new();
+        if !self.dir.exists() {
+            return Ok(entries);
+        }
+        for shard in std::fs::read_dir(&self.dir)? {
+            let shard = shard?.path();
+            if !shard.is_dir() {
+                continue;
+            }
+            for file in std::fs::read_dir(&shard)? {
+                let path = file?.path();
+                if path.extension().is_some_and(|ext| ext == "json") {
+                    let entry = read_entry(&path)?;
+                    entries.push((path, entry));
+                }
+            }
+        }
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(entries)
+    }
+
+    /// Remove entries by the conditions, and return the count of removed ones.
+    pub fn gc(&self, gc: &Gc) -> Result<usize> {
+        ensure!(
+            gc.all || gc.has_condition(),
+            "Refuse to remove all entries without a condition: pass `--all` to do so"
+        );
+        let now = now_secs();
+        let mut removed = 0;
+        for (path, entry) in self.entries()? {
+            if gc.removes(&entry, now) {
+                std::fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+fn read_entry(path: &Path) -> Result<CacheEntry> {
+    let text = std::fs::read_to_string(path)?;
+    serde_json::from_str(&text).with_context(|| format!("Failed to parse cache entry {path:?}"))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+/// Lowercase hex of the SHA-256 digest.
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{b:02x}")).collect()
+}
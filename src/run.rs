@@ -0,0 +1,306 @@
+//! Run kani on selected proofs in parallel.
+
+use crate::{
+    Kind, ProofView, Result,
+    attrs::KaniAttrs,
+    deps::{DepIssue, ProofGraph},
+    filter::read_rerun,
+    read_proofs,
+    result_cache::{ResultCache, sha256_hex},
+    write_json,
+};
+use eyre::Context;
+use serde::{Deserialize, Serialize};
+use std::{
+    path::PathBuf,
+    process::Command,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Instant,
+};
+
+/// Run `kani --harness <name> --exact` for each proof with bounded parallelism.
+#[derive(clap::Args, Debug)]
+pub struct RunArgs {
+    /// JSON file of proofs, e.g. the full output, a filtered one, or a shard.
+    pub file: String,
+
+    /// Only run proofs in the rerun list of a JSON emitted by `diff`.
+    #[arg(long)]
+    pub rerun: Option<String>,
+
+    /// Max count of kani processes at the same time. Default to available parallelism.
+    #[arg(long, short)]
+    pub jobs: Option<usize>,
+
+    /// Directory of the result cache. Proofs with successful results under the same
+    /// kani version and kani args are skipped, and new successful results are inserted.
+    #[arg(long)]
+    pub cache: Option<String>,
+
+    #[command(flatten)]
+    pub runner: Runner,
+
+    /// Path to write the run report JSON. Print to stdout if not set.
+    #[arg(long)]
+    pub json: Option<String>,
+}
+
+impl RunArgs {
+    pub fn run(&self) -> Result<()> {
+        let proofs = read_proofs(&self.file)?;
+        let views = proofs.views();
+        // The graph is built before filtering, so contract proofs that don't rerun are known.
+        let graph = ProofGraph::new(&views);
+        let rerun = self.rerun.as_deref().map(read_rerun).transpose()?;
+        let selected = graph.select(rerun.as_ref());
+        // contract proofs are started before proofs using their contracts
+        let jobs: Vec<_> = selected.iter().map(|&i| Job::new(&views[i])).collect();
+        let parallelism = match self.jobs {
+            Some(n) => n,
+            None => std::thread::available_parallelism()?.get(),
+        };
+        info!(proofs = jobs.len(), parallelism);
+
+        let mut report = match &self.cache {
+            Some(dir) => self.runner.run_all_cached(&jobs, parallelism, &ResultCache::new(dir))?,
+            None => self.runner.run_all(&jobs, parallelism)?,
+        };
+        report.dependency_issues = graph.run_issues(&selected, &report);
+        write_json(self.json.as_deref(), &report)?;
+
+        let failed = report.results.iter().filter(|r| !r.success).count();
+        ensure!(failed == 0, "{failed} of {} proofs failed", report.results.len());
+        Ok(())
+    }
+}
+
+/// Read a run report JSON emitted by `run` or `coordinator`.
+pub fn read_report(path: &str) -> Result<RunReport> {
+    let _span = debug_span!("read_report", path).entered();
+    let file = std::fs::File::open(path).with_context(|| format!("Failed to open {path}"))?;
+    Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+}
+
+/// A proof to verify.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub file: String,
+    pub name: String,
+    pub hash: String,
+    pub kind: Option<Kind>,
+    /// Extra kani args for the proof, such as unstable features it needs.
+    pub kani_args: Vec<String>,
+}
+
+impl Job {
+    pub fn new(proof: &ProofView) -> Self {
+        let mut kani_args = Vec::new();
+        let kind = proof.kind;
+        let attrs = KaniAttrs::parse(proof.attrs);
+        if attrs.needs_function_contracts() {
+            kani_args.push("-Zfunction-contracts".to_owned());
+        }
+        if attrs.needs_stubbing() {
+            kani_args.push("-Zstubbing".to_owned());
+        }
+        if kind == Some(Kind::LoopContract) {
+            kani_args.push("-Zloop-contracts".to_owned());
+        }
+        Job {
+            file: proof.file.to_owned(),
+            name: proof.name.to_owned(),
+            hash: proof.hash.to_owned(),
+            kind,
+            kani_args,
+        }
+    }
+}
+
+/// How to spawn kani for a proof.
+#[derive(clap::Args, Debug, Clone)]
+pub struct Runner {
+    /// The kani executable.
+    #[arg(long, default_value = "kani")]
+    pub kani: String,
+
+    /// Run `cargo kani` in the current directory instead of `kani <file>`.
+    #[arg(long, default_value_t = false)]
+    pub cargo: bool,
+
+    /// Extra args passed to kani for all proofs.
+    #[arg(long, allow_hyphen_values = true)]
+    pub kani_args: Vec<String>,
+
+    /// Directory to write kani output of each proof to.
+    #[arg(long, default_value = "target/distributed-verification/logs")]
+    pub log_dir: String,
+}
+
+/// Results of running kani on proofs.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct RunReport {
+    /// Output of `kani --version`.
+    pub kani_version: String,
+    /// Extra kani args for all proofs, i.e. `--kani-args` of the runner.
+    #[serde(default)]
+    pub kani_args: Vec<String>,
+    /// Results in the order of jobs. `run` starts contract proofs before proofs
+    /// using their contracts, and keeps the input order otherwise.
+    pub results: Vec<ProofResult>,
+    /// Proofs in the run using a contract through `stub_verified` that is never
+    /// checked or whose proof failed.
+    #[serde(default)]
+    pub dependency_issues: Vec<DepIssue>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ProofResult {
+    pub file: String,
+    pub name: String,
+    pub hash: String,
+    /// Does kani exit successfully?
+    pub success: bool,
+    /// Exit code of kani. None if kani is terminated by a signal or fails to spawn.
+    pub exit_code: Option<i32>,
+    /// Wall time in seconds.
+    pub duration_secs: f64,
+    /// Path to the file of kani's stdout and stderr.
+    pub log: String,
+    /// SHA-256 digest of kani's stdout and stderr.
+    #[serde(default)]
+    pub log_digest: String,
+    /// Is the result from the cache instead of running kani?
+    #[serde(default)]
+    pub cached: bool,
+}
+
+impl Runner {
+    /// Output of `kani --version`.
+    pub fn kani_version(&self) -> Result<String> {
+        let mut cmd = self.command();
+        let out =
+            cmd.arg("--version").output().with_context(|| format!("Failed to run {cmd:?}"))?;
+        Ok(String::from_utf8_lossy(&out.stdout).trim().to_owned())
+    }
+
+    fn command(&self) -> Command {
+        if self.cargo {
+            let mut cmd = Command::new("cargo");
+            cmd.arg("kani");
+            cmd
+        } else {
+            Command::new(&self.kani)
+        }
+    }
+
+    /// Run all jobs with at most `parallelism` kani processes at the same time.
+    pub fn run_all(&self, jobs: &[Job], parallelism: usize) -> Result<RunReport> {
+        std::fs::create_dir_all(&self.log_dir)?;
+        let kani_version = self.kani_version()?;
+
+        let next = AtomicUsize::new(0);
+        let results = Mutex::new(vec![None; jobs.len()]);
+        std::thread::scope(|s| {
+            for _ in 0..parallelism.clamp(1, jobs.len().max(1)) {
+                s.spawn(|| {
+                    loop {
+                        let pos = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(job) = jobs.get(pos) else { break };
+                        let result = self.run(job);
+                        results.lock().unwrap()[pos] = Some(result);
+                    }
+                });
+            }
+        });
+
+        let results = results.into_inner().unwrap().into_iter().map(Option::unwrap).collect();
+        let kani_args = self.kani_args.clone();
+        Ok(RunReport { kani_version, kani_args, results, dependency_issues: Vec::new() })
+    }
+
+    /// Like `run_all`, but reuse results in the cache, and insert new results.
+    pub fn run_all_cached(
+        &self,
+        jobs: &[Job],
+        parallelism: usize,
+        cache: &ResultCache,
+    ) -> Result<RunReport> {
+        let kani_version = self.kani_version()?;
+        let mut results = Vec::with_capacity(jobs.len());
+        let mut misses = Vec::new();
+        for job in jobs {
+            let entry = cache.get(&job.hash, &kani_version, &self.kani_args)?;
+            if entry.is_none() {
+                misses.push(job.clone());
+            }
+            results.push(entry.map(ProofResult::from));
+        }
+        info!(hits = jobs.len() - misses.len(), misses = misses.len());
+
+        let report = self.run_all(&misses, parallelism)?;
+        ensure!(
+            report.kani_version == kani_version,
+            "kani version changed during the run: {kani_version:?} vs {:?}",
+            report.kani_version
+        );
+        cache.insert_report(&report)?;
+
+        let mut fresh = report.results.into_iter();
+        let results = results.into_iter().map(|r| r.or_else(|| fresh.next()).unwrap()).collect();
+        let kani_args = self.kani_args.clone();
+        Ok(RunReport { kani_version, kani_args, results, dependency_issues: Vec::new() })
+    }
+
+    /// Run kani on a single proof.
+    pub fn run(&self, job: &Job) -> ProofResult {
+        let _span = error_span!("run", job.name).entered();
+        let log = self.log_path(job);
+        let mut cmd = self.command();
+        if !self.cargo {
+            cmd.arg(&job.file);
+        }
+        cmd.args(["--harness", &job.name, "--exact"]).args(&job.kani_args).args(&self.kani_args);
+
+        let now = Instant::now();
+        let output = cmd.output();
+        let duration_secs = now.elapsed().as_secs_f64();
+
+        let (success, exit_code, text) = match output {
+            Ok(out) => {
+                let mut text = out.stdout;
+                text.extend(out.stderr);
+                (out.status.success(), out.status.code(), text)
+            }
+            Err(err) => (false, None, format!("Failed to run {cmd:?}: {err}").into_bytes()),
+        };
+        let log_digest = sha256_hex(&text);
+        if let Err(err) = std::fs::write(&log, text) {
+            error!(?log, ?err, "Failed to write kani output");
+        }
+        info!(success, duration_secs);
+
+        ProofResult {
+            file: job.file.clone(),
+            name: job.name.clone(),
+            hash: job.hash.clone(),
+            success,
+            exit_code,
+            duration_secs,
+            log: log.display().to_string(),
+            log_digest,
+            cached: false,
+        }
+    }
+
+    fn log_path(&self, job: &Job) -> PathBuf {
+        let name: String =
+            job.name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+        // e.g. `sip128:0123…`, where the prefix would make every name start the same
+        let hex = job.hash.split_once(':').map_or(&*job.hash, |(_, hex)| hex);
+        let hash = hex.get(..16).unwrap_or(hex);
+        PathBuf::from(&self.log_dir).join(format!("{name}-{hash}.log"))
+    }
+}
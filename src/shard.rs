@@ -0,0 +1,117 @@
+//! Split proofs into shards balanced by estimated cost for distributed runs.
+
+use crate::{ProofView, Proofs, Result, read_proofs, write_json};
+use serde::{Deserialize, Serialize};
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+/// Split proofs into N shards, balanced by estimated cost.
+#[derive(clap::Args, Debug)]
+pub struct ShardArgs {
+    /// JSON file of proofs.
+    pub file: String,
+
+    /// Count of shards.
+    #[arg(long, short = 'n')]
+    pub shards: usize,
+
+    /// Directory to write `shard-{index}.json` files to. Each file has the
+    /// same format as the input, and only contains proofs in that shard.
+    #[arg(long)]
+    pub out_dir: Option<String>,
+
+    /// Path to write shard plan JSON, which can be used as a CI matrix.
+    /// Print to stdout if not set.
+    #[arg(long)]
+    pub json: Option<String>,
+}
+
+impl ShardArgs {
+    pub fn run(&self) -> Result<()> {
+        ensure!(self.shards > 0, "--shards must be positive");
+        let proofs = read_proofs(&self.file)?;
+        let plan = ShardPlan::new(&proofs, self.shards);
+        info!(proofs = proofs.len(), shards = self.shards, costs = ?plan.costs());
+
+        if let Some(dir) = &self.out_dir {
+            std::fs::create_dir_all(dir)?;
+            for shard in &plan.include {
+                let path = format!("{dir}/shard-{}.json", shard.index);
+                let indices: Vec<_> = shard.proofs.iter().map(|p| p.position).collect();
+                write_json(Some(&path), &proofs.select(&indices))?;
+            }
+        }
+        write_json(self.json.as_deref(), &plan)
+    }
+}
+
+/// Shards of proofs. The field is named `include` to be used as a GitHub Actions matrix.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ShardPlan {
+    pub include: Vec<Shard>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Shard {
+    pub index: usize,
+    /// Sum of estimated costs of proofs in the shard.
+    pub cost: u64,
+    pub proofs: Vec<ShardProof>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ShardProof {
+    pub file: String,
+    pub name: String,
+    pub hash: String,
+    pub cost: u64,
+    /// Position in the input JSON.
+    pub position: usize,
+}
+
+/// Estimated cost to verify a proof.
+///
+/// It's the cost score from MIR if available, otherwise the count of callees.
+/// One is added so that proofs without callees still count.
+pub fn estimate_cost(proof: &ProofView) -> u64 {
+    proof.cost.unwrap_or(proof.callees.len() as u64) + 1
+}
+
+impl ShardPlan {
+    /// Assign proofs to shards through longest processing time first:
+    /// the most expensive proof goes to the shard with the least cost so far.
+    pub fn new(proofs: &Proofs, shards: usize) -> Self {
+        let mut v: Vec<_> = proofs
+            .views()
+            .iter()
+            .enumerate()
+            .map(|(position, p)| ShardProof {
+                file: p.file.to_owned(),
+                name: p.name.to_owned(),
+                hash: p.hash.to_owned(),
+                cost: estimate_cost(p),
+                position,
+            })
+            .collect();
+        // stable sort to keep the input order for the same cost
+        v.sort_by_key(|p| Reverse(p.cost));
+
+        let mut include: Vec<_> =
+            (0..shards).map(|index| Shard { index, ..Default::default() }).collect();
+        // min-heap on (cost, index)
+        let mut heap: BinaryHeap<_> = (0..shards).map(|index| Reverse((0, index))).collect();
+        for proof in v {
+            let Reverse((cost, index)) = heap.pop().unwrap();
+            let shard = &mut include[index];
+            shard.cost = cost + proof.cost;
+            shard.proofs.push(proof);
+            heap.push(Reverse((shard.cost, index)));
+        }
+        // proofs in a shard follow the input order
+        include.iter_mut().for_each(|s| s.proofs.sort_by_key(|p| p.position));
+        ShardPlan { include }
+    }
+
+    pub fn costs(&self) -> Vec<u64> {
+        self.include.iter().map(|s| s.cost).collect()
+    }
+}
@@ -0,0 +1,82 @@
+//! Summarize proofs in a JSON output.
+
+use crate::{
+    Kind, Proofs, Result,
+    kani_list::{check_proofs, read_kani_list},
+    read_proofs, write_json,
+};
+use indexmap::{IndexMap, IndexSet};
+use serde::{Deserialize, Serialize};
+
+/// Count proofs and callees in a JSON file.
+#[derive(clap::Args, Debug)]
+pub struct SummaryArgs {
+    /// JSON file of proofs.
+    pub file: String,
+
+    /// Compare proofs with a kani-list.json. Only full JSON is supported.
+    #[arg(long)]
+    pub check_kani_list: Option<String>,
+
+    /// Path to write the summary JSON. Print to stdout if not set.
+    #[arg(long)]
+    pub json: Option<String>,
+}
+
+impl SummaryArgs {
+    pub fn run(&self) -> Result<()> {
+        let proofs = read_proofs(&self.file)?;
+        if let Some(path) = &self.check_kani_list {
+            let Proofs::Full(v) = &proofs else {
+                bail!("--check-kani-list needs full JSON, but {} is simplified", self.file)
+            };
+            check_proofs(&read_kani_list(path)?, v)?;
+        }
+        write_json(self.json.as_deref(), &Summary::new(&proofs))
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Summary {
+    /// Count of proofs.
+    pub proofs: usize,
+    /// Count of `#[kani::proof]`.
+    pub standard: usize,
+    /// Count of `#[kani::proof_for_contract]`.
+    pub contract: usize,
+    /// Count of proofs with loop contracts.
+    #[serde(default)]
+    pub loop_contract: usize,
+    /// Count of proofs generated by macros.
+    #[serde(default)]
+    pub generated: usize,
+    /// Count of distinct callees across all proofs.
+    pub unique_callees: usize,
+    /// The largest count of callees in a proof.
+    pub max_callees: usize,
+    /// Count of proofs in each file, sorted by file path.
+    pub files: IndexMap<String, usize>,
+}
+
+impl Summary {
+    pub fn new(proofs: &Proofs) -> Self {
+        let mut summary = Summary::default();
+        let mut callees = IndexSet::new();
+        for proof in proofs.views() {
+            summary.proofs += 1;
+            match proof.kind {
+                Some(Kind::Standard) => summary.standard += 1,
+                Some(Kind::Contract) => summary.contract += 1,
+                Some(Kind::LoopContract) => summary.loop_contract += 1,
+                Some(Kind::Generated) => summary.generated += 1,
+                None => (),
+            }
+            summary.max_callees = summary.max_callees.max(proof.callees.len());
+            callees.extend(proof.callees);
+            *summary.files.entry(proof.file.to_owned()).or_default() += 1;
+        }
+        summary.unique_callees = callees.len();
+        summary.files.sort_unstable_keys();
+        summary
+    }
+}
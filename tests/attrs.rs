@@ -0,0 +1,101 @@
+use distributed_verification::{
+    Kind, SimplifiedSerFunction,
+    attrs::{KaniAttrs, attr_value},
+};
+
+mod utils;
+use utils::{assert_eq, *};
+
+#[test]
+fn parse_kani_attrs() {
+    let attrs = [
+        "#[kanitool::proof_for_contract = \"verify::checked_div\"]",
+        "#[kanitool::unwind(4)]",
+        "#[kanitool::solver(kissat)]",
+        "#[kanitool::should_panic]",
+        "#[kanitool::stub(std::vec::Vec::<u8>::push, verify::push, )]",
+        "#[kanitool::stub_verified(verify::checked_div)]",
+        "#[kanitool::is_contract_generated(check)]",
+        "#[kanitool::unwind(many)]",
+        "#[allow(unused)]",
+    ]
+    .map(String::from);
+    let attrs = KaniAttrs::parse(&attrs);
+    assert!(attrs.is_proof() && attrs.needs_stubbing() && attrs.needs_function_contracts());
+    expect![[r##"
+        KaniAttrs {
+            proof: false,
+            proof_for_contract: Some(
+                "verify::checked_div",
+            ),
+            unwind: Some(
+                4,
+            ),
+            solver: Some(
+                "kissat",
+            ),
+            should_panic: true,
+            stubs: [
+                Stub {
+                    original: "std::vec::Vec::<u8>::push",
+                    replacement: "verify::push",
+                },
+            ],
+            stub_verified: [
+                "verify::checked_div",
+            ],
+            recursion: false,
+            requires: [],
+            ensures: [],
+            modifies: [],
+            loop_invariant: [],
+            other: [
+                "#[kanitool::is_contract_generated(check)]",
+                "#[kanitool::unwind(many)]",
+            ],
+        }
+    "##]]
+    .assert_debug_eq(&attrs);
+
+    let attrs = KaniAttrs::parse(&["#[kanitool::proof]".to_owned()]);
+    assert!(attrs.is_proof() && !attrs.needs_stubbing() && !attrs.needs_function_contracts());
+}
+
+#[test]
+fn kani_attr_value() {
+    let attr = "#[kanitool::replaced_with = \"__kani_replace_div\"]";
+    assert_eq!(attr_value(attr, "replaced_with").as_deref(), Some("__kani_replace_div"));
+    assert_eq!(attr_value(attr, "checked_with"), None);
+    assert_eq!(attr_value("#[kanitool::proof]", "proof"), None);
+}
+
+#[test]
+fn proof_kinds() {
+    let kind =
+        |attrs: &[&str]| Kind::from_attrs(&attrs.iter().map(|a| a.to_string()).collect::<Vec<_>>());
+    assert_eq!(kind(&["#[kanitool::proof]"]), Some(Kind::Standard));
+    assert_eq!(kind(&["#[kanitool::proof_for_contract = \"f\"]"]), Some(Kind::Contract));
+    assert_eq!(
+        kind(&["#[kanitool::proof]", "#[kanitool::loop_invariant(i <= n)]"]),
+        Some(Kind::LoopContract)
+    );
+    // not a proof, nor a prefix match of `proof`
+    assert_eq!(kind(&["#[kanitool::unwind(4)]"]), None);
+    assert_eq!(kind(&["#[kanitool::proofs]"]), None);
+}
+
+#[test]
+fn simplified_proof_kind() {
+    let attrs = vec!["#[kanitool::proof]".to_owned()];
+    let proof = SimplifiedSerFunction { attrs, kind: Some(Kind::Generated), ..Default::default() };
+    assert_eq!(proof.view().kind, Some(Kind::Generated));
+
+    // simplified JSON emitted before `kind` was added
+    let json = r##"{"hash": "", "attrs": ["#[kanitool::proof]"], "name": "f", "file": "lib.rs",
+        "callees_len": 0, "callees": []}"##;
+    let proof: SimplifiedSerFunction = serde_json::from_str(json).unwrap();
+    assert_eq!(proof.view().kind, Some(Kind::Standard));
+    let json =
+        serde_json::to_string(&SimplifiedSerFunction::from(&SerFunction::default())).unwrap();
+    assert!(json.contains(r#""kind":"Standard""#), "{json}");
+}
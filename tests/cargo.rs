@@ -0,0 +1,107 @@
+use distributed_verification::{
+    cargo::{CrateProofs, CrateTarget, ENV_OUT_DIR, STD_LIBRARY_JSON},
+    kani_version_of,
+};
+
+mod utils;
+use utils::{assert_eq, *};
+
+fn json_name(args: &str) -> Option<String> {
+    let args: Vec<_> = args.split(' ').map(String::from).collect();
+    CrateTarget::new(&args).map(|c| c.json_name())
+}
+
+#[test]
+fn rustc_args_from_cargo() {
+    // rustc queries
+    assert_eq!(json_name("-vV"), None);
+    assert_eq!(
+        json_name("- --crate-name ___ --print=file-names --crate-type bin --crate-type rlib"),
+        None
+    );
+
+    // build scripts and proc macros
+    assert_eq!(
+        json_name("--crate-name build_script_build --edition=2024 build.rs --crate-type bin"),
+        None
+    );
+    assert_eq!(json_name("--crate-name derive --crate-type proc-macro src/lib.rs"), None);
+
+    let names = [
+        "--crate-name dummy_crate --edition=2024 src/lib.rs --crate-type lib --emit=dep-info,metadata,link",
+        "--crate-name dummy_crate --edition=2024 src/main.rs --crate-type bin",
+        "--crate-name dummy_crate --edition=2024 src/lib.rs --test",
+        "--crate-name=dummy_crate --crate-type=rlib src/lib.rs",
+        "--crate-name std --crate-type rlib --crate-type dylib src/lib.rs",
+    ]
+    .map(json_name);
+    expect![[r#"
+        [
+            Some(
+                "dummy_crate-lib.json",
+            ),
+            Some(
+                "dummy_crate-bin.json",
+            ),
+            Some(
+                "dummy_crate-test.json",
+            ),
+            Some(
+                "dummy_crate-lib.json",
+            ),
+            Some(
+                "std-lib.json",
+            ),
+        ]
+    "#]]
+    .assert_debug_eq(&names);
+}
+
+#[test]
+fn rustc_wrapper_without_args() {
+    let out_dir = std::env::temp_dir().join("dv-wrapper-without-args");
+    let output = assert_cmd::Command::cargo_bin(env!("CARGO_PKG_NAME"))
+        .unwrap()
+        .env(ENV_OUT_DIR, &out_dir)
+        .env("RUST_LOG", "off")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("No rustc path for the rustc wrapper"), "{stderr}");
+    assert!(!stderr.contains("panicked"), "{stderr}");
+}
+
+#[test]
+fn kani_version_from_kani() -> Result<()> {
+    // a development build isn't named by the version
+    let kani_dir = std::env::temp_dir().join("dv-kani-version/kani/target/kani");
+    _ = std::fs::remove_dir_all(&kani_dir);
+    fake_kani(&kani_dir.join("bin"), "echo 'Kani Rust Verifier 0.62.0 (standalone)'\n")?;
+    let version = kani_version_of(kani_dir.to_str().unwrap());
+    assert_eq!(version, "Kani Rust Verifier 0.62.0 (standalone)");
+    Ok(())
+}
+
+/// Analyze standard library crates through a dummy crate built with `-Zbuild-std`.
+/// std is built as both rlib and dylib.
+#[test]
+#[ignore = "needs kani and verify-rust-std at VERIFY_RUST_STD_LIBRARY"]
+fn std_library() -> Result<()> {
+    let library = std::env::var("VERIFY_RUST_STD_LIBRARY")?;
+    let out_dir = std::env::temp_dir().join("dv-std-library");
+    _ = std::fs::remove_dir_all(&out_dir);
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_cargo-distributed-verification"))
+        .args(["distributed-verification", "--std-library", &library])
+        .args(["--std-crates", "core,std", "--simplify-json", "--out-dir"])
+        .arg(&out_dir)
+        .env("RUST_LOG", "off")
+        .status()?;
+    assert!(status.success());
+
+    let text = std::fs::read_to_string(out_dir.join(STD_LIBRARY_JSON))?;
+    let crates: CrateProofs = serde_json::from_str(&text)?;
+    assert_eq!(crates.keys().collect::<Vec<_>>(), ["core", "std"]);
+    assert!(!crates["core"].is_empty());
+    Ok(())
+}
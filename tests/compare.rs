@@ -1,4 +1,4 @@
-use std::fs::{copy, remove_file};
+use std::{fs, path::PathBuf};
 
 mod utils;
 use utils::{assert_eq, *};
@@ -11,21 +11,37 @@ fn get(text: &str, start: &str) -> SerFunction {
 
 const COMPARE: &str = "tests/compare";
 
+/// A directory in the system temp dir, which is specific to `tmp`.
+fn tmp_dir(tmp: &str) -> PathBuf {
+    std::env::temp_dir().join("distributed-verification-compare").join(tmp)
+}
+
+/// Analyze each file with extra args, and return the output. Each file is copied to
+/// `tests/compare/{tmp}.rs` in a temp dir, so the path and crate name stay the same
+/// among files, and nothing is written into the source tree.
+fn analyze(tmp: &str, v_file: &[&str], args: &[&str]) -> Vec<String> {
+    let dir = tmp_dir(tmp);
+    let path = format!("{COMPARE}/{tmp}.rs");
+    fs::create_dir_all(dir.join(COMPARE)).unwrap();
+    v_file
+        .iter()
+        .map(|ele| {
+            fs::copy(format!("{COMPARE}/{ele}.rs"), dir.join(&path)).unwrap();
+            cmd_in(&dir, &[&[&*path], args].concat())
+        })
+        .collect()
+}
+
 fn compare(tmp: &str, v_file: &[&str], f: &str, assert: impl Fn(&SerFunction, &SerFunction, &str)) {
     let len = v_file.len();
     assert!(len > 1);
-    let tmp = format!("{COMPARE}/{tmp}.rs");
 
     let mut v_func = vec![];
-    for ele in v_file {
-        copy(format!("{COMPARE}/{ele}.rs"), &tmp).unwrap();
-        let text = cmd(&[&tmp]);
+    for (ele, text) in v_file.iter().zip(analyze(tmp, v_file, &[])) {
         expect_file![format!("./snapshots/{ele}.json")].assert_eq(&text);
         v_func.push(get(&text, f));
     }
 
-    remove_file(tmp).unwrap();
-
     // For the same proof (w.r.t same path and body),
     // the hash value must be the same.
     for i in 0..len - 1 {
@@ -66,6 +82,178 @@ fn test_compare() {
     );
 }
 
+/// The same proof from each file, analyzed with extra args.
+fn functions(tmp: &str, v_file: &[&str], f: &str, args: &[&str]) -> Vec<SerFunction> {
+    analyze(tmp, v_file, args).iter().map(|text| get(text, f)).collect()
+}
+
+/// Hash values of the same proof from each file, analyzed with extra args.
+fn hashes(tmp: &str, v_file: &[&str], f: &str, args: &[&str]) -> Vec<String> {
+    functions(tmp, v_file, f, args).into_iter().map(|func| func.hash).collect()
+}
+
+#[test]
+fn test_compare_hash_mode() {
+    // proof3 is proof4 with formatting and comments changed, which moves the closure
+    let files = ["proof4", "proof3"];
+    let source = hashes("hash_mode_source", &files, "pub fn f()", &[]);
+    assert_ne!(source[0], source[1]);
+    let tokens = hashes("hash_mode_tokens", &files, "pub fn f()", &["--hash-mode", "tokens"]);
+    assert_eq!(tokens[0], tokens[1]);
+    let mir = hashes("hash_mode_mir", &files, "pub fn f()", &["--hash-mode", "mir"]);
+    assert_eq!(mir[0], mir[1]);
+}
+
+#[test]
+fn test_compare_hash_profile() {
+    // the same file built with different crate metadata
+    let hash = |args: &[&str]| hashes("hash_profile", &["proof1"], "pub fn f()", args).remove(0);
+    assert_ne!(hash(&["--", "-Cmetadata=a"]), hash(&["--", "-Cmetadata=b"]));
+    assert_eq!(
+        hash(&["--hash-profile", "portable", "--", "-Cmetadata=a"]),
+        hash(&["--hash-profile", "portable", "--", "-Cmetadata=b"]),
+    );
+}
+
+#[test]
+fn test_compare_hash_profile_const_args() {
+    // `one::<3>` and `one::<5>` have the same body, and only differ by names
+    let args = ["--hash-profile", "portable"];
+    let func = functions("const_args", &["const_args1"], "pub fn f()", &args).remove(0);
+    let callees: Vec<_> = func.callees.iter().filter(|c| c.func.name.contains("one::<")).collect();
+    assert_eq!(callees.len(), 2);
+    assert_ne!(callees[0].hash, callees[1].hash);
+}
+
+#[test]
+fn test_compare_hash_algorithm() {
+    let hash = |args: &[&str]| hashes("hash_algorithm", &["proof1"], "pub fn f()", args).remove(0);
+    let fixed_width = |hash: &str, prefix: &str, len: usize| {
+        let hex = hash.strip_prefix(prefix).unwrap_or_default();
+        assert!(hex.len() == len && hex.bytes().all(|b| b.is_ascii_hexdigit()), "{hash}");
+    };
+    fixed_width(&hash(&[]), "sip128:", 32);
+    fixed_width(&hash(&["--hash-algorithm", "sha256"]), "sha256:", 64);
+}
+
+#[test]
+fn test_compare_hash_env() {
+    let func = |args: &[&str]| functions("hash_env", &["proof1"], "pub fn f()", args).remove(0);
+    // an unused cfg doesn't change the code, but the environment
+    let (a, b) = (["--", "--cfg=env_a"], ["--", "--cfg=env_b"]);
+    let (f_a, f_b) = (func(&a), func(&b));
+    assert_ne!(f_a.env, f_b.env);
+    assert_eq!(f_a.hash, f_b.hash);
+
+    let with_env = |args: &[&str]| func(&[&["--hash-env"], args].concat()).hash;
+    assert_ne!(with_env(&a), with_env(&b));
+}
+
+#[test]
+fn test_compare_explain_hash() {
+    let dir = tmp_dir("explain").join("dumps");
+    let args = ["--explain-hash", dir.to_str().unwrap(), "--explain-proof", "verify::f"];
+    let dumps: Vec<_> = ["proof1", "proof3"]
+        .iter()
+        .map(|file| {
+            let func = functions("explain", &[file], "pub fn f()", &args).remove(0);
+            let dump = fs::read_to_string(dir.join("verify-f.txt")).unwrap();
+            // the proof hash comes first, and the function hash is explained too
+            assert!(dump.starts_with(&func.hash), "{dump}");
+            assert!(dump.contains(&format!("\n{}\n", func.func_hash)), "{dump}");
+            dump
+        })
+        .collect();
+    fs::remove_dir_all(dir).unwrap();
+    // proof3 only changes formatting and comments of proof1
+    assert_ne!(dumps[0], dumps[1]);
+}
+
+#[test]
+fn test_compare_stubs() {
+    // stub2 changes the stubbed function, and stub3 changes the replacement
+    let v_hash = hashes("stub", &["stub1", "stub2", "stub3"], "pub fn f()", &[]);
+    assert_eq!(v_hash[0], v_hash[1]);
+    assert_ne!(v_hash[0], v_hash[2]);
+
+    // stub5 changes another function named `random`, which isn't stubbed
+    let v_func = functions("stub", &["stub4", "stub5"], "pub fn f()", &[]);
+    assert_ne!(v_func[0].hash, v_func[1].hash);
+    assert!(v_func[0].callees.iter().any(|c| c.func.name == "verify::other::random"));
+
+    // stub6 stubs inherent methods of primitive types
+    let func = functions("stub", &["stub6"], "pub fn f()", &[]).remove(0);
+    let names: Vec<_> = func.callees.iter().map(|c| &*c.func.name).collect();
+    assert!(names.contains(&"verify::unchecked_add_stub"), "{names:?}");
+    assert!(names.contains(&"verify::ptr_add_stub::<u8>"), "{names:?}");
+    assert!(!names.iter().any(|name| name.ends_with(">::unchecked_add")), "{names:?}");
+}
+
+#[test]
+fn test_compare_consts_and_statics() {
+    // static2 changes a const, and static3 changes a static
+    let files = ["static1", "static2", "static3"];
+    for args in [&[][..], &["--hash-mode", "mir"]] {
+        let v_hash = hashes("static", &files, "pub fn f()", args);
+        assert_ne!(v_hash[0], v_hash[1], "{args:?}");
+        assert_ne!(v_hash[0], v_hash[2], "{args:?}");
+    }
+}
+
+#[test]
+fn test_compare_types() {
+    // adt2 changes a field type, and adt3 adds `#[repr(C)]`
+    let files = ["adt1", "adt2", "adt3"];
+    for args in [&[][..], &["--hash-mode", "mir"]] {
+        let v_hash = hashes("adt", &files, "pub fn f()", args);
+        assert_ne!(v_hash[0], v_hash[1], "{args:?}");
+        assert_ne!(v_hash[0], v_hash[2], "{args:?}");
+    }
+}
+
+#[test]
+fn test_compare_opaque() {
+    // opaque2 changes the signature of a foreign function
+    let files = ["opaque1", "opaque2"];
+    let v_hash = hashes("opaque", &files, "pub fn f()", &[]);
+    assert_ne!(v_hash[0], v_hash[1]);
+}
+
+#[test]
+fn test_compare_stub_verified() {
+    // verified2 changes the `requires` clause of the contract, and verified3 changes the body
+    // of the target, which kani replaces with the contract for `stub_verified`
+    let files = ["verified1", "verified2", "verified3"];
+    let checks = functions("verified", &files, "pub fn check_div()", &[]);
+    let users = functions("verified", &files, "pub fn use_div()", &[]);
+
+    let contract = checks[0].contract.as_ref().unwrap();
+    assert!(contract.target.ends_with("::verify::div"), "{contract:?}");
+    assert!(!contract.closures.is_empty());
+
+    let stub = &users[0].stub_verified[0];
+    assert_eq!(stub.target, contract.target);
+    assert_eq!(stub.proofs, ["verify::check_div"]);
+
+    assert_ne!(checks[0].hash, checks[1].hash);
+    assert_ne!(users[0].hash, users[1].hash);
+    assert_ne!(checks[0].hash, checks[2].hash);
+    assert_eq!(users[0].hash, users[2].hash);
+    assert!(!users[0].callees.iter().any(|c| c.func.name == "verify::div"));
+}
+
+#[test]
+fn test_compare_cost() {
+    // cost1 adds a loop, a `kani::any()` call and `#[kani::unwind(3)]` to cost2,
+    // and callees are the same, so the differences only come from the proof body
+    let v_func = functions("cost", &["cost1", "cost2"], "pub fn f()", &[]);
+    let (cost1, cost2) = (&v_func[0].cost, &v_func[1].cost);
+    assert_eq!(cost1.back_edges, cost2.back_edges + 1);
+    assert_eq!(cost1.kani_any, cost2.kani_any + 1);
+    assert_eq!((cost1.unwind, cost2.unwind), (Some(3), None));
+    assert!(cost1.score > cost2.score);
+}
+
 fn simplify_ser_function(fn1: &SerFunction) -> SerFunction {
     SerFunction {
         hash: fn1.hash.clone(),
@@ -0,0 +1,17 @@
+#[cfg(kani)]
+mod verify {
+    pub struct Pair {
+        pub a: u8,
+        pub b: u16,
+    }
+
+    fn sum(p: &Pair) -> u32 {
+        p.a as u32 + p.b as u32
+    }
+
+    #[kani::proof]
+    pub fn f() {
+        let p = Pair { a: kani::any(), b: kani::any() };
+        assert!(sum(&p) >= p.a as u32);
+    }
+}
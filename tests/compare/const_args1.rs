@@ -0,0 +1,12 @@
+#[cfg(kani)]
+mod verify {
+    // instances only differ by const args
+    fn one<const N: usize>() -> usize {
+        1
+    }
+
+    #[kani::proof]
+    pub fn f() {
+        assert_eq!(one::<3>() + one::<5>(), 2);
+    }
+}
@@ -0,0 +1,15 @@
+#[cfg(kani)]
+mod verify {
+    #[kani::proof]
+    #[kani::unwind(3)]
+    pub fn f() {
+        let n: u8 = kani::any();
+        let m: u8 = kani::any();
+        kani::assume(n < 3);
+        let mut i = 0;
+        while i < n {
+            i += 1;
+        }
+        assert!(i == n || m == 0);
+    }
+}
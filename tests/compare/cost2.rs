@@ -0,0 +1,13 @@
+#[cfg(kani)]
+mod verify {
+    #[kani::proof]
+    pub fn f() {
+        let n: u8 = kani::any();
+        kani::assume(n < 3);
+        let mut i = 0;
+        if i < n {
+            i += 1;
+        }
+        assert!(i <= n);
+    }
+}
@@ -0,0 +1,12 @@
+#[cfg(kani)]
+mod verify {
+    unsafe extern "C" {
+        fn abs(x: i32) -> i32;
+    }
+
+    #[kani::proof]
+    pub fn f() {
+        let x = kani::any_where(|x| *x > 0);
+        assert!(unsafe { abs(x) } == x);
+    }
+}
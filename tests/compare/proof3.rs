@@ -0,0 +1,16 @@
+#[cfg(kani)]
+mod verify {
+    /// Same as proof4.rs, but reformatted with comments.
+    #[kani::proof]
+    pub fn f() {
+        // a comment
+        let a   =   1;
+        let add_one =
+            |x: u8|
+                x   +   1;
+        assert_eq!(
+            add_one(a),
+            2
+        );
+    }
+}
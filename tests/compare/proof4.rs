@@ -0,0 +1,9 @@
+#[cfg(kani)]
+mod verify {
+    #[kani::proof]
+    pub fn f() {
+        let a = 1;
+        let add_one = |x: u8| x + 1;
+        assert_eq!(add_one(a), 2);
+    }
+}
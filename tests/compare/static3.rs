@@ -0,0 +1,11 @@
+#[cfg(kani)]
+mod verify {
+    const TABLE: [u8; 4] = [1, 2, 3, 4];
+    static LIMIT: u8 = 11;
+
+    #[kani::proof]
+    pub fn f() {
+        let i: usize = kani::any_where(|i| *i < 4);
+        assert!(TABLE[i] < LIMIT);
+    }
+}
@@ -0,0 +1,16 @@
+#[cfg(kani)]
+mod verify {
+    fn random() -> u8 {
+        std::process::id() as u8
+    }
+
+    fn mock_random() -> u8 {
+        4
+    }
+
+    #[kani::proof]
+    #[kani::stub(random, mock_random)]
+    pub fn f() {
+        assert!(random() == 4);
+    }
+}
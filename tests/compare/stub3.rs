@@ -0,0 +1,16 @@
+#[cfg(kani)]
+mod verify {
+    fn random() -> u8 {
+        (std::process::id() % 256) as u8
+    }
+
+    fn mock_random() -> u8 {
+        2 + 2
+    }
+
+    #[kani::proof]
+    #[kani::stub(random, mock_random)]
+    pub fn f() {
+        assert!(random() == 4);
+    }
+}
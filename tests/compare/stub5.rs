@@ -0,0 +1,22 @@
+#[cfg(kani)]
+mod verify {
+    mod other {
+        pub fn random() -> u8 {
+            2
+        }
+    }
+
+    fn random() -> u8 {
+        (std::process::id() % 256) as u8
+    }
+
+    fn mock_random() -> u8 {
+        4
+    }
+
+    #[kani::proof]
+    #[kani::stub(random, mock_random)]
+    pub fn f() {
+        assert!(random() + other::random() >= 5);
+    }
+}
@@ -0,0 +1,20 @@
+#[cfg(kani)]
+mod verify {
+    unsafe fn unchecked_add_stub(a: u8, b: u8) -> u8 {
+        a.wrapping_add(b)
+    }
+
+    unsafe fn ptr_add_stub<T>(p: *const T, count: usize) -> *const T {
+        p.wrapping_add(count)
+    }
+
+    #[kani::proof]
+    #[kani::stub(u8::unchecked_add, unchecked_add_stub)]
+    #[kani::stub(<*const T>::add, ptr_add_stub)]
+    pub fn f() {
+        let arr = [1u8, 2];
+        let x = unsafe { 1u8.unchecked_add(2) };
+        let y = unsafe { *arr.as_ptr().add(1) };
+        assert!(x == 3 && y == 2);
+    }
+}
@@ -0,0 +1,19 @@
+#[cfg(kani)]
+mod verify {
+    #[kani::requires(b != 0)]
+    #[kani::ensures(|&ret| ret <= a)]
+    pub fn div(a: u8, b: u8) -> u8 {
+        a / b
+    }
+
+    #[kani::proof_for_contract(div)]
+    pub fn check_div() {
+        div(kani::any(), kani::any());
+    }
+
+    #[kani::proof]
+    #[kani::stub_verified(div)]
+    pub fn use_div() {
+        assert!(div(4, 2) <= 4);
+    }
+}
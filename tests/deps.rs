@@ -0,0 +1,101 @@
+use distributed_verification::{
+    Proofs, SimplifiedSerFunction,
+    deps::{IssueKind, ProofGraph},
+    run::{ProofResult, RunReport},
+};
+
+mod utils;
+use utils::assert_eq;
+
+fn proof(name: &str, attrs: &[&str]) -> SimplifiedSerFunction {
+    SimplifiedSerFunction {
+        hash: format!("hash-{name}"),
+        attrs: attrs.iter().map(|a| a.to_string()).collect(),
+        name: name.to_owned(),
+        file: "src/lib.rs".to_owned(),
+        ..Default::default()
+    }
+}
+
+/// A proof with contract targets resolved to def paths.
+fn resolved(name: &str, contract: Option<&str>, stub_verified: &[&str]) -> SimplifiedSerFunction {
+    let attr = match contract {
+        Some(_) => "#[kanitool::proof_for_contract = \"div\"]",
+        None => "#[kanitool::proof]",
+    };
+    SimplifiedSerFunction {
+        contract: contract.map(str::to_owned),
+        stub_verified: stub_verified.iter().map(|s| s.to_string()).collect(),
+        ..proof(name, &[attr])
+    }
+}
+
+#[test]
+fn contract_proofs_first() {
+    let proofs = Proofs::Simplified(vec![
+        resolved("verify::use_div", None, &["a[1234]::verify::div"]),
+        // the same name in another module is another function
+        resolved("verify::use_other_div", None, &["a[1234]::other::div"]),
+        resolved("verify::check_div", Some("a[1234]::verify::div"), &[]),
+    ]);
+    let mut graph = ProofGraph::new(&proofs.views());
+    assert_eq!(graph.order, [1, 2, 0]);
+    assert_eq!(graph.proofs[0].stub_verified[0].proofs, [2]);
+    assert!(graph.proofs[1].stub_verified[0].proofs.is_empty());
+    assert_eq!(graph.proofs[2].contract.as_deref(), Some("a[1234]::verify::div"));
+
+    let result = |name: &str, success| ProofResult {
+        file: "src/lib.rs".to_owned(),
+        name: name.to_owned(),
+        hash: format!("hash-{name}"),
+        success,
+        ..Default::default()
+    };
+    let report = RunReport {
+        results: vec![result("verify::check_div", false), result("verify::use_div", true)],
+        ..Default::default()
+    };
+    graph.check_failed(&report);
+    let issues: Vec<_> =
+        graph.issues.iter().map(|i| (&*i.name, i.kind, i.contract_proof.as_deref())).collect();
+    assert_eq!(
+        issues,
+        [
+            ("verify::use_other_div", IssueKind::Missing, None),
+            ("verify::use_div", IssueKind::Failed, Some("verify::check_div")),
+        ]
+    );
+}
+
+#[test]
+fn unresolved_targets() {
+    // paths as written are compared exactly
+    let proofs = Proofs::Simplified(vec![
+        proof("verify::use_div", &["#[kanitool::proof]", "#[kanitool::stub_verified(div)]"]),
+        proof("verify::use_read", &["#[kanitool::proof]", "#[kanitool::stub_verified(read)]"]),
+        proof("verify::check_div", &["#[kanitool::proof_for_contract = \"div\"]"]),
+        proof("verify::check_read", &["#[kanitool::proof_for_contract = \"io::read\"]"]),
+    ]);
+    let graph = ProofGraph::new(&proofs.views());
+    assert_eq!(graph.order, [1, 2, 0, 3]);
+    assert_eq!(graph.proofs[0].stub_verified[0].proofs, [2]);
+    assert!(graph.proofs[1].stub_verified[0].proofs.is_empty());
+}
+
+#[test]
+fn contract_cycle() {
+    let proofs = Proofs::Simplified(vec![
+        proof(
+            "verify::check_f",
+            &["#[kanitool::proof_for_contract = \"f\"]", "#[kanitool::stub_verified(g)]"],
+        ),
+        proof(
+            "verify::check_g",
+            &["#[kanitool::proof_for_contract = \"g\"]", "#[kanitool::stub_verified(f)]"],
+        ),
+    ]);
+    let graph = ProofGraph::new(&proofs.views());
+    assert_eq!(graph.order, [0, 1]);
+    assert!(graph.issues.iter().all(|i| i.kind == IssueKind::Cycle));
+    assert_eq!(graph.issues.len(), 2);
+}
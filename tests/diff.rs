@@ -0,0 +1,180 @@
+use distributed_verification::{Opaque, Proofs, Static, TypeDef, diff::ProofDiff, read_proofs};
+
+mod utils;
+use utils::{assert_eq, *};
+
+fn diff_files(old: &str, new: &str) -> Result<ProofDiff> {
+    let old = read_proofs(old)?.into_simplified();
+    let new = read_proofs(new)?.into_simplified();
+    Ok(ProofDiff::new(&old, &new))
+}
+
+#[test]
+fn diff_added_proof() -> Result<()> {
+    let diff = diff_files("tests/snapshots/proof1.json", "tests/snapshots/proof2.json")?;
+    expect![[r#"
+        ProofDiff {
+            rerun: [
+                DiffProof {
+                    file: "tests/compare/proof.rs",
+                    name: "verify::g",
+                    old_hash: None,
+                    new_hash: Some(
+                        "sip128:5668fddec43569f8b6e38f4a870517e9",
+                    ),
+                    changes: None,
+                },
+            ],
+            added: [
+                DiffProof {
+                    file: "tests/compare/proof.rs",
+                    name: "verify::g",
+                    old_hash: None,
+                    new_hash: Some(
+                        "sip128:5668fddec43569f8b6e38f4a870517e9",
+                    ),
+                    changes: None,
+                },
+            ],
+            removed: [],
+            hash_changed: [],
+            unchanged: [
+                DiffProof {
+                    file: "tests/compare/proof.rs",
+                    name: "verify::f",
+                    old_hash: Some(
+                        "sip128:1006d57a40cb36392719e6d8d557b292",
+                    ),
+                    new_hash: Some(
+                        "sip128:1006d57a40cb36392719e6d8d557b292",
+                    ),
+                    changes: None,
+                },
+            ],
+        }
+    "#]]
+    .assert_debug_eq(&diff);
+
+    // swap old and new
+    let diff = diff_files("tests/snapshots/proof2.json", "tests/snapshots/proof1.json")?;
+    assert!(diff.rerun.is_empty());
+    assert_eq!(diff.removed.len(), 1);
+    assert_eq!(diff.removed[0].name, "verify::g");
+    Ok(())
+}
+
+#[test]
+fn diff_prefixed_hash() -> Result<()> {
+    let old = analyze_no_kani("proofs", &[]).into_simplified();
+    let new = analyze_no_kani("proofs", &["--hash-algorithm", "sha256"]).into_simplified();
+    let is_hex = |hash: &str, prefix: &str, len: usize| {
+        let hex = hash.strip_prefix(prefix).unwrap_or_default();
+        hex.len() == len && hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+    };
+    assert!(old.iter().all(|f| is_hex(&f.hash, "sip128:", 32)), "{old:#?}");
+    assert!(new.iter().all(|f| is_hex(&f.hash, "sha256:", 64)), "{new:#?}");
+
+    // the same proofs hashed by another algorithm are all rerun
+    let diff = ProofDiff::new(&old, &new);
+    assert_eq!(diff.hash_changed.len(), old.len());
+    assert_eq!(diff.rerun, diff.hash_changed);
+    assert!(diff.added.is_empty() && diff.removed.is_empty());
+
+    // the same digits from another algorithm aren't the same hash
+    let mut new = old.clone();
+    let hex = old[0].hash.strip_prefix("sip128:").unwrap();
+    new[0].hash = format!("sha256:{hex}");
+    let diff = ProofDiff::new(&old, &new);
+    assert_eq!(diff.hash_changed.len(), 1);
+    assert_eq!(diff.hash_changed[0].old_hash.as_deref(), Some(&*old[0].hash));
+    assert_eq!(diff.rerun, diff.hash_changed);
+    Ok(())
+}
+
+#[test]
+fn diff_full_and_simplified() -> Result<()> {
+    let proofs = get_proofs("tests/proofs")?;
+    for path in &proofs {
+        let file_stem = file_stem(path);
+        let full = format!("tests/snapshots/{file_stem}.json");
+        if !std::fs::exists(&full)? {
+            continue;
+        }
+        let simplified = format!("tests/simplified/{file_stem}.json");
+        let diff = diff_files(&full, &simplified)?;
+        assert!(diff.rerun.is_empty(), "{file_stem}: {diff:#?}");
+        assert!(diff.removed.is_empty(), "{file_stem}: {diff:#?}");
+    }
+    Ok(())
+}
+
+#[test]
+fn diff_changed_functions() -> Result<()> {
+    let proofs = read_proofs("tests/snapshots/standard_proofs.json")?;
+    let Proofs::Full(old) = proofs else { panic!("full JSON is expected") };
+    let mut old: Vec<_> =
+        old.into_iter().filter(|f| f.func.name == "verify::standard_proof").collect();
+    for f in &mut old {
+        f.func_hash = "proof".into();
+        f.callees.iter_mut().for_each(|c| c.hash = c.func.name.clone());
+    }
+
+    old[0].statics = vec![Static { name: "LIMIT".into(), ty: "u8".into(), hash: "1".into() }];
+    old[0].types = vec![
+        TypeDef { name: "verify::Pair".into(), hash: "1".into() },
+        TypeDef { name: "verify::Unchanged".into(), hash: "1".into() },
+    ];
+    let abs = Opaque { kind: "Item".into(), name: "verify::abs".into(), ..Default::default() };
+    old[0].opaque = vec![abs];
+
+    let mut new = old.clone();
+    let f = &mut new[0];
+    f.statics[0].hash = "2".into();
+    f.types[0].hash = "2".into();
+    f.opaque[0].hash = "changed".into();
+    f.hash = "changed".into();
+    f.callees.retain(|c| c.func.name != "kani::assert");
+    f.callees[0].hash = "changed".into();
+    let mut added = f.callees[1].clone();
+    added.func.name = "kani::any_new".into();
+    f.callees.push(added);
+
+    let mut diff = ProofDiff::new(
+        &Proofs::Full(old.clone()).to_simplified(),
+        &Proofs::Full(new.clone()).to_simplified(),
+    );
+    diff.explain(&old, &new);
+    expect![[r#"
+        Some(
+            FnChanges {
+                proof: false,
+                added: [
+                    "kani::any_new",
+                ],
+                removed: [
+                    "kani::assert",
+                ],
+                changed: [
+                    "<u8 as kani::Arbitrary>::any",
+                ],
+                statics: [
+                    "LIMIT",
+                ],
+                types: [
+                    "verify::Pair",
+                ],
+                opaque: [
+                    "verify::abs",
+                ],
+            },
+        )
+    "#]]
+    .assert_debug_eq(&diff.hash_changed[0].changes);
+    assert_eq!(diff.rerun, diff.hash_changed);
+
+    // unknown if function hashes are missing
+    new[0].func_hash.clear();
+    diff.explain(&old, &new);
+    assert!(diff.hash_changed[0].changes.is_none());
+    Ok(())
+}
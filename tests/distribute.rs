@@ -0,0 +1,155 @@
+use distributed_verification::{
+    SimplifiedSerFunction,
+    deps::IssueKind,
+    distribute::{Coordinator, Response, Worker},
+    read_proofs,
+    run::{Job, read_report},
+};
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    time::Duration,
+};
+
+mod utils;
+use utils::{assert_eq, *};
+
+/// Spawn a coordinator and two worker processes with a fake kani that succeeds.
+#[test]
+fn coordinator_and_workers() -> Result<()> {
+    let dir = std::env::temp_dir().join("dv-distribute");
+    _ = std::fs::remove_dir_all(&dir);
+    let kani = fake_kani(&dir, "echo \"$@\"\n")?;
+
+    let proofs = read_proofs("tests/simplified/proofs_for_contract.json")?;
+    let jobs: Vec<_> = proofs.views().iter().map(Job::new).collect();
+    let coordinator = Coordinator::bind("127.0.0.1:0", jobs, Duration::from_secs(60))?;
+    let addr = coordinator.local_addr()?.to_string();
+    let server = std::thread::spawn(move || coordinator.serve());
+
+    let workers: Vec<_> = (0..2)
+        .map(|i| {
+            std::process::Command::new(env!("CARGO_BIN_EXE_dv-offline"))
+                .env("RUST_LOG", "off")
+                .args(["worker", "--connect", &addr, "--name", &format!("w{i}")])
+                .args(["--kani", kani.to_str().unwrap()])
+                .args(["--log-dir", dir.join(format!("logs-{i}")).to_str().unwrap()])
+                .spawn()
+        })
+        .collect::<Result<_, _>>()?;
+    for mut worker in workers {
+        assert!(worker.wait()?.success());
+    }
+
+    let report = server.join().unwrap()?;
+    assert_eq!(report.kani_version, "--version");
+    let names: Vec<_> = report.results.iter().map(|r| (&*r.name, r.success)).collect();
+    expect![[r#"
+        [
+            (
+                "verify::single_contract",
+                true,
+            ),
+            (
+                "verify::single_contract_requires",
+                true,
+            ),
+            (
+                "verify::single_with_contract_ensures",
+                true,
+            ),
+            (
+                "verify::two_contracts_requires_and_ensures",
+                true,
+            ),
+        ]
+    "#]]
+    .assert_debug_eq(&names);
+    let log = std::fs::read_to_string(&report.results[0].log)?;
+    expect![[r#"
+        tests/proofs/proofs_for_contract.rs --harness verify::single_contract --exact -Zfunction-contracts
+    "#]]
+    .assert_eq(&log);
+    Ok(())
+}
+
+/// The coordinator hands out contract proofs first, and reports proofs relying on
+/// a failed contract proof.
+#[test]
+fn coordinator_follows_contract_dependencies() -> Result<()> {
+    let dir = std::env::temp_dir().join("dv-distribute-deps");
+    _ = std::fs::remove_dir_all(&dir);
+    let kani = fake_kani(&dir, "case \"$*\" in *check_div*) exit 1;; *) echo \"$@\";; esac\n")?;
+
+    let proof = |name: &str, attr: &str, contract: Option<&str>, stub_verified: &[&str]| {
+        SimplifiedSerFunction {
+            hash: format!("hash-{name}"),
+            attrs: vec![attr.to_owned()],
+            name: name.to_owned(),
+            file: "src/lib.rs".to_owned(),
+            contract: contract.map(str::to_owned),
+            stub_verified: stub_verified.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    };
+    let proofs = [
+        proof("verify::use_div", "#[kanitool::proof]", None, &["a[1234]::verify::div"]),
+        proof(
+            "verify::check_div",
+            "#[kanitool::proof_for_contract = \"div\"]",
+            Some("a[1234]::verify::div"),
+            &[],
+        ),
+    ];
+    let file = dir.join("proofs.json");
+    std::fs::write(&file, serde_json::to_string(&proofs)?)?;
+
+    // a free port for the coordinator
+    let addr = TcpListener::bind("127.0.0.1:0")?.local_addr()?.to_string();
+    let report = dir.join("report.json");
+    let mut coordinator = std::process::Command::new(env!("CARGO_BIN_EXE_dv-offline"))
+        .env("RUST_LOG", "off")
+        .args(["coordinator", file.to_str().unwrap(), "--listen", &addr])
+        .args(["--json", report.to_str().unwrap()])
+        .stderr(std::process::Stdio::null())
+        .spawn()?;
+    let worker = std::process::Command::new(env!("CARGO_BIN_EXE_dv-offline"))
+        .env("RUST_LOG", "off")
+        .args(["worker", "--connect", &addr, "--kani", kani.to_str().unwrap()])
+        .args(["--log-dir", dir.join("logs").to_str().unwrap()])
+        .status()?;
+    assert!(worker.success());
+    // the contract proof fails
+    assert!(!coordinator.wait()?.success());
+
+    let report = read_report(report.to_str().unwrap())?;
+    let names: Vec<_> = report.results.iter().map(|r| (&*r.name, r.success)).collect();
+    assert_eq!(names, [("verify::check_div", false), ("verify::use_div", true)]);
+    let issues: Vec<_> = report.dependency_issues.iter().map(|i| (&*i.name, i.kind)).collect();
+    assert_eq!(issues, [("verify::use_div", IssueKind::Failed)]);
+    Ok(())
+}
+
+/// A worker fails if the coordinator goes away without saying done.
+#[test]
+fn worker_loses_coordinator() -> Result<()> {
+    let dir = std::env::temp_dir().join("dv-distribute-lost");
+    _ = std::fs::remove_dir_all(&dir);
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?.to_string();
+    // answer the first pull with Wait, and close the listener
+    let server = std::thread::spawn(move || -> Result<()> {
+        let (stream, _) = listener.accept()?;
+        BufReader::new(&stream).read_line(&mut String::new())?;
+        let mut line = serde_json::to_string(&Response::Wait)?;
+        line.push('\n');
+        (&stream).write_all(line.as_bytes())?;
+        Ok(())
+    });
+    let runner = fake_runner(&dir, "echo \"$@\"\n")?;
+    let worker = Worker { addr, name: "w".into(), cache: None, runner };
+    let err = worker.run().unwrap_err();
+    server.join().unwrap()?;
+    assert_eq!(err.to_string(), "Lost the coordinator");
+    Ok(())
+}
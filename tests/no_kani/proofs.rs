@@ -0,0 +1,30 @@
+//! Proofs with `#[kanitool::*]` attributes written out, like kani's proc macros expand
+//! `#[kani::*]` attributes, so they're analyzed with `--no-kani-args` when kani isn't installed.
+#![feature(register_tool)]
+#![register_tool(kanitool)]
+
+pub fn add(a: u8, b: u8) -> u8 {
+    a.wrapping_add(b)
+}
+
+pub fn sum(v: &[u8]) -> u8 {
+    let mut s = 0;
+    for &x in v {
+        s = add(s, x);
+    }
+    s
+}
+
+#[kanitool::proof]
+pub fn proof_add() {
+    assert!(add(1, 2) == 3);
+}
+
+#[kanitool::proof]
+#[kanitool::unwind(4)]
+pub fn proof_sum() {
+    assert!(sum(&[1, 2, 3]) == 6);
+}
+
+#[kanitool::proof]
+pub fn proof_empty() {}
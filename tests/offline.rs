@@ -0,0 +1,58 @@
+use distributed_verification::{Proofs, merge::merge, read_proofs, summary::Summary};
+
+mod utils;
+use utils::{assert_eq, *};
+
+#[test]
+fn merge_proofs() -> Result<()> {
+    let proof1 = read_proofs("tests/snapshots/proof1.json")?;
+    let proof2 = read_proofs("tests/snapshots/proof2.json")?;
+
+    // verify::f is in both files, and only kept once
+    let merged = merge(vec![proof1.clone(), proof2.clone()])?;
+    assert!(matches!(merged, Proofs::Full(_)));
+    let names: Vec<_> = merged.views().iter().map(|p| p.name).collect();
+    assert_eq!(names, ["verify::f", "verify::g"]);
+
+    // mixed inputs result in simplified JSON
+    let simplified = read_proofs("tests/simplified/ad_hoc.json")?;
+    assert!(matches!(simplified, Proofs::Simplified(_)));
+    let merged = merge(vec![proof2, simplified])?;
+    assert!(matches!(merged, Proofs::Simplified(_)));
+    assert_eq!(merged.len(), 5);
+
+    // the same proof with different hash values
+    let mut modified = proof1.clone();
+    if let Proofs::Full(v) = &mut modified {
+        v[0].hash.push('0');
+    }
+    let err = merge(vec![proof1, modified]).unwrap_err();
+    expect![[r#"
+        The proof ("tests/compare/proof.rs", "verify::f") has different hash values: sip128:1006d57a40cb36392719e6d8d557b292 vs sip128:1006d57a40cb36392719e6d8d557b2920"#]]
+    .assert_eq(&err.to_string());
+
+    Ok(())
+}
+
+#[test]
+fn summarize_proofs() -> Result<()> {
+    let full = Summary::new(&read_proofs("tests/snapshots/standard_proofs.json")?);
+    let simplified = Summary::new(&read_proofs("tests/simplified/standard_proofs.json")?);
+    assert_eq!(serde_json::to_string(&full).unwrap(), serde_json::to_string(&simplified).unwrap());
+    expect![[r#"
+        Summary {
+            proofs: 3,
+            standard: 3,
+            contract: 0,
+            loop_contract: 0,
+            generated: 0,
+            unique_callees: 113,
+            max_callees: 107,
+            files: {
+                "tests/proofs/standard_proofs.rs": 3,
+            },
+        }
+    "#]]
+    .assert_debug_eq(&full);
+    Ok(())
+}
@@ -34,17 +34,26 @@ fn extract_macros_items(json: &[SerFunction]) -> Vec<SerFunction> {
             let callees: Vec<_> = j
                 .callees
                 .iter()
-                .filter(|callee| callee.def_id.contains("\"verify::"))
+                .filter(|callee| callee.def_id.contains("]::verify::"))
                 .cloned()
                 .collect();
             SerFunction {
                 hash: j.hash.clone(),
                 def_id: j.def_id.clone(),
                 attrs: j.attrs.clone(),
+                kani_attrs: j.kani_attrs.clone(),
                 kind: j.kind,
                 func: j.func.clone(),
+                func_hash: j.func_hash.clone(),
                 callees_len: callees.len(),
+                cost: j.cost.clone(),
                 callees,
+                statics: j.statics.clone(),
+                types: j.types.clone(),
+                opaque: j.opaque.clone(),
+                contract: j.contract.clone(),
+                stub_verified: j.stub_verified.clone(),
+                env: j.env.clone(),
             }
         })
         .collect()
@@ -98,12 +107,34 @@ fn test_compare_unique_hash() -> Result<()> {
 
     expect![[r#"
         [
+            "tests/compare/adt1.rs",
+            "tests/compare/adt2.rs",
+            "tests/compare/adt3.rs",
+            "tests/compare/const_args1.rs",
             "tests/compare/contract1.rs",
             "tests/compare/contract2.rs",
+            "tests/compare/cost1.rs",
+            "tests/compare/cost2.rs",
             "tests/compare/gen_proofs_by_nested_macros1.rs",
             "tests/compare/gen_proofs_by_nested_macros2.rs",
+            "tests/compare/opaque1.rs",
+            "tests/compare/opaque2.rs",
             "tests/compare/proof1.rs",
             "tests/compare/proof2.rs",
+            "tests/compare/proof3.rs",
+            "tests/compare/proof4.rs",
+            "tests/compare/static1.rs",
+            "tests/compare/static2.rs",
+            "tests/compare/static3.rs",
+            "tests/compare/stub1.rs",
+            "tests/compare/stub2.rs",
+            "tests/compare/stub3.rs",
+            "tests/compare/stub4.rs",
+            "tests/compare/stub5.rs",
+            "tests/compare/stub6.rs",
+            "tests/compare/verified1.rs",
+            "tests/compare/verified2.rs",
+            "tests/compare/verified3.rs",
         ]
     "#]]
     .assert_debug_eq(&proofs);
@@ -0,0 +1,108 @@
+use distributed_verification::{
+    read_proofs,
+    result_cache::{Gc, ResultCache},
+    run::{Job, Runner},
+};
+
+mod utils;
+use utils::{assert_eq, *};
+
+/// A fake kani that appends the harness name to a file and exits with the given code.
+fn runner(dir: &std::path::Path, exit_code: i32) -> Result<Runner> {
+    let calls = dir.join("calls");
+    let body =
+        format!("[ \"$2\" = --harness ] && echo \"$3\" >> {calls:?}\necho ok\nexit {exit_code}\n");
+    fake_runner(dir, &body)
+}
+
+fn calls(dir: &std::path::Path) -> Result<Vec<String>> {
+    let text = std::fs::read_to_string(dir.join("calls")).unwrap_or_default();
+    Ok(text.lines().map(String::from).collect())
+}
+
+#[test]
+fn skip_cached_proofs() -> Result<()> {
+    let dir = std::env::temp_dir().join("dv-result-cache");
+    _ = std::fs::remove_dir_all(&dir);
+    let runner = runner(&dir, 0)?;
+    let cache = ResultCache::new(dir.join("cache"));
+    let proofs = read_proofs("tests/simplified/standard_proofs.json")?;
+    let jobs: Vec<_> = proofs.views().iter().map(Job::new).collect();
+
+    // only run the first proof, and cache its result
+    let report = runner.run_all_cached(&jobs[..1], 2, &cache)?;
+    assert!(report.results.iter().all(|r| r.success && !r.cached));
+    assert_eq!(calls(&dir)?, ["verify::recursive_callees"]);
+
+    let entry = cache.get(&jobs[0].hash, &report.kani_version, &[])?.unwrap();
+    assert_eq!(entry.log_digest, report.results[0].log_digest);
+    assert_eq!(entry.log_digest.len(), 64);
+    assert!(cache.get(&jobs[0].hash, "another kani", &[])?.is_none());
+    // kani args are part of the key
+    assert!(cache.get(&jobs[0].hash, &report.kani_version, &["--unwind=1".into()])?.is_none());
+
+    // the cached proof is skipped, and results keep the input order
+    let report = runner.run_all_cached(&jobs, 2, &cache)?;
+    let results: Vec<_> = report.results.iter().map(|r| (&*r.name, r.cached)).collect();
+    expect![[r#"
+        [
+            (
+                "verify::recursive_callees",
+                true,
+            ),
+            (
+                "verify::standard_proof",
+                false,
+            ),
+            (
+                "verify::standard_proof_empty",
+                false,
+            ),
+        ]
+    "#]]
+    .assert_debug_eq(&results);
+    assert_eq!(calls(&dir)?.len(), 3);
+
+    // nothing is run the third time
+    let report = runner.run_all_cached(&jobs, 2, &cache)?;
+    assert!(report.results.iter().all(|r| r.cached));
+    assert_eq!(calls(&dir)?.len(), 3);
+
+    // gc
+    assert_eq!(cache.entries()?.len(), 3);
+    let gc = Gc { kani_version: Some(report.kani_version.clone()), ..Default::default() };
+    assert_eq!(cache.gc(&gc)?, 0);
+    let keep = Some([jobs[1].hash.clone()].into_iter().collect());
+    assert_eq!(cache.gc(&Gc { keep, ..Default::default() })?, 2);
+    // removing everything must be asked for
+    expect!["Refuse to remove all entries without a condition: pass `--all` to do so"]
+        .assert_eq(&cache.gc(&Gc::default()).unwrap_err().to_string());
+    assert_eq!(cache.entries()?.len(), 1);
+    assert_eq!(cache.gc(&Gc { all: true, ..Default::default() })?, 1);
+    assert!(cache.entries()?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn failures_are_not_cached() -> Result<()> {
+    let dir = std::env::temp_dir().join("dv-result-cache-failures");
+    _ = std::fs::remove_dir_all(&dir);
+    let cache = ResultCache::new(dir.join("cache"));
+    let proofs = read_proofs("tests/simplified/standard_proofs.json")?;
+    let jobs: Vec<_> = proofs.views().iter().map(Job::new).collect();
+
+    // failures may be transient, so they're run again
+    let failing = runner(&dir, 1)?;
+    for _ in 0..2 {
+        let report = failing.run_all_cached(&jobs[..1], 1, &cache)?;
+        assert!(report.results.iter().all(|r| !r.success && !r.cached));
+    }
+    assert_eq!(calls(&dir)?.len(), 2);
+    assert!(cache.entries()?.is_empty());
+
+    let report = runner(&dir, 0)?.run_all_cached(&jobs[..1], 1, &cache)?;
+    assert!(report.results[0].success);
+    assert_eq!(cache.entries()?.len(), 1);
+    Ok(())
+}
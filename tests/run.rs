@@ -0,0 +1,60 @@
+use distributed_verification::{
+    read_proofs,
+    run::{Job, Runner},
+};
+
+mod utils;
+use utils::{assert_eq, *};
+
+/// A fake kani that prints its args and exits with the given code.
+fn runner(exit_code: i32) -> Result<Runner> {
+    let dir = std::env::temp_dir().join(format!("dv-run-{exit_code}"));
+    let runner = fake_runner(&dir, &format!("echo \"$@\"\nexit {exit_code}\n"))?;
+    Ok(Runner { kani_args: vec!["--quiet".into()], ..runner })
+}
+
+fn read_jobs(file: &str) -> Result<Vec<Job>> {
+    Ok(read_proofs(file)?.views().iter().map(Job::new).collect())
+}
+
+#[test]
+fn run_with_fake_kani() -> Result<()> {
+    let jobs = read_jobs("tests/simplified/standard_proofs.json")?;
+    let report = runner(0)?.run_all(&jobs, 2)?;
+    assert_eq!(report.kani_version, "--version");
+    let names: Vec<_> = report.results.iter().map(|r| (&*r.name, r.success)).collect();
+    expect![[r#"
+        [
+            (
+                "verify::recursive_callees",
+                true,
+            ),
+            (
+                "verify::standard_proof",
+                true,
+            ),
+            (
+                "verify::standard_proof_empty",
+                true,
+            ),
+        ]
+    "#]]
+    .assert_debug_eq(&names);
+    let log = std::fs::read_to_string(&report.results[1].log)?;
+    expect![[r#"
+        tests/proofs/standard_proofs.rs --harness verify::standard_proof --exact --quiet
+    "#]]
+    .assert_eq(&log);
+    // logs are named by the harness and the first hex digits of the hash
+    let hex = &jobs[1].hash.strip_prefix("sip128:").unwrap()[..16];
+    let file_name = format!("verify__standard_proof-{hex}.log");
+    assert!(report.results[1].log.ends_with(&file_name), "{}", report.results[1].log);
+
+    // contract proofs need an unstable flag
+    let jobs = read_jobs("tests/simplified/proofs_for_contract.json")?;
+    assert!(jobs.iter().all(|j| j.kani_args == ["-Zfunction-contracts"]));
+
+    let report = runner(1)?.run_all(&jobs, 8)?;
+    assert!(report.results.iter().all(|r| !r.success && r.exit_code == Some(1)));
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use distributed_verification::shard::ShardPlan;
+
+mod utils;
+use utils::{assert_eq, *};
+
+fn plan(proofs: &Proofs, shards: usize) -> Vec<Vec<(String, u64)>> {
+    let plan = ShardPlan::new(proofs, shards);
+    assert_eq!(plan.include.len(), shards);
+    let total: usize = plan.include.iter().map(|s| s.proofs.len()).sum();
+    assert_eq!(total, proofs.len());
+    plan.include
+        .iter()
+        .map(|s| s.proofs.iter().map(|p| (p.name.clone(), p.cost)).collect())
+        .collect()
+}
+
+#[test]
+fn balanced_shards() {
+    let proofs = analyze_no_kani("proofs", &["--simplify-json"]);
+    expect![[r#"
+        [
+            [
+                (
+                    "proof_sum",
+                    141,
+                ),
+            ],
+            [
+                (
+                    "proof_add",
+                    8,
+                ),
+                (
+                    "proof_empty",
+                    2,
+                ),
+            ],
+        ]
+    "#]]
+    .assert_debug_eq(&plan(&proofs, 2));
+
+    // empty shards if there are more shards than proofs
+    let shards = plan(&proofs, 4);
+    assert_eq!(shards.iter().filter(|s| s.is_empty()).count(), 1);
+}
@@ -2,14 +2,20 @@
 use assert_cmd::Command;
 use std::path::{Path, PathBuf};
 
-pub use distributed_verification::SerFunction;
+use distributed_verification::run::Runner;
+pub use distributed_verification::{Proofs, SerFunction};
 pub use expect_test::{expect, expect_file};
 pub use eyre::Result;
 pub use pretty_assertions::assert_eq;
 
 pub fn cmd(args: &[&str]) -> String {
+    cmd_in(Path::new("."), args)
+}
+
+/// Run the driver in `dir`, so relative paths in args are resolved from it.
+pub fn cmd_in(dir: &Path, args: &[&str]) -> String {
     let mut command = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
-    command.env("RUST_LOG", "off").args(args);
+    command.env("RUST_LOG", "off").current_dir(dir).args(args);
     let output = command.output().unwrap();
     assert!(
         output.status.success(),
@@ -20,6 +26,14 @@ pub fn cmd(args: &[&str]) -> String {
     String::from_utf8(output.stdout).unwrap()
 }
 
+/// Analyze a file under `tests/no_kani` with extra driver args. Its proofs have
+/// `#[kanitool::*]` attributes written out, so neither kani nor its rustc args are needed.
+pub fn analyze_no_kani(file: &str, args: &[&str]) -> Proofs {
+    let file = format!("tests/no_kani/{file}.rs");
+    let args = [&["--no-kani-args"], args, &["--", &*file, "--crate-type=lib"]].concat();
+    serde_json::from_str(&cmd(&args)).unwrap()
+}
+
 /// Get rs files under a dir.
 pub fn get_proofs(dir: &str) -> Result<Vec<PathBuf>> {
     let mut proofs = vec![];
@@ -39,3 +53,25 @@ pub fn get_proofs(dir: &str) -> Result<Vec<PathBuf>> {
 pub fn file_stem(path: &Path) -> &str {
     path.file_stem().and_then(|f| f.to_str()).unwrap()
 }
+
+/// Write a fake kani to `dir`, which is a shell script with the body.
+/// It's called with the same args as the real kani, e.g. `--version`, or
+/// `<file> --harness <name> --exact ...`.
+pub fn fake_kani(dir: &Path, body: &str) -> Result<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::create_dir_all(dir)?;
+    let kani = dir.join("kani");
+    std::fs::write(&kani, format!("#!/bin/sh\n{body}"))?;
+    std::fs::set_permissions(&kani, std::fs::Permissions::from_mode(0o755))?;
+    Ok(kani)
+}
+
+/// A runner of a fake kani with the body, writing logs under `dir`.
+pub fn fake_runner(dir: &Path, body: &str) -> Result<Runner> {
+    Ok(Runner {
+        kani: fake_kani(dir, body)?.display().to_string(),
+        cargo: false,
+        kani_args: vec![],
+        log_dir: dir.join("logs").display().to_string(),
+    })
+}